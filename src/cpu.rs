@@ -0,0 +1,604 @@
+//! 从 /proc/cpuinfo 和 /sys/devices/system/cpu 读取 CPU 拓扑，不依赖 lscpu
+
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::CpuInfo;
+use crate::sysfs;
+
+/// 与机器学习相关的指令集，x86 取自 flags，ARM 取自 Features
+const ML_FLAGS: &[&str] = &[
+    "avx", "avx2", "fma", "f16c",
+    "avx512f", "avx512bw", "avx512vl", "avx512_vnni", "avx512_bf16", "avx512_fp16", "avx_vnni",
+    "amx_tile", "amx_bf16", "amx_int8",
+    "asimd", "asimdhp", "asimddp", "bf16", "i8mm", "sve", "sve2", "sme",
+];
+
+/// /proc/cpuinfo 中的一个逻辑 CPU，键值原样保存
+pub type CpuinfoEntry = BTreeMap<String, String>;
+
+/// 单个逻辑 CPU 的频率，单位 MHz
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct CoreFrequency {
+    pub cpu: u32,
+    pub cur_mhz: Option<u32>,
+    pub min_mhz: Option<u32>,
+    pub max_mhz: Option<u32>,
+    /// 调频策略，如 performance、powersave、schedutil
+    pub governor: Option<String>,
+}
+
+/// 通过 procfs 和 sysfs 获取 CPU 信息
+pub struct CpuSysfs {
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+}
+
+impl Default for CpuSysfs {
+    fn default() -> Self {
+        CpuSysfs::with_root("/proc", "/sys/devices/system/cpu")
+    }
+}
+
+impl CpuSysfs {
+    /// 指定 /proc 和 /sys/devices/system/cpu 的位置，测试时可以指向采集到的文件
+    pub fn with_root<P: Into<PathBuf>, S: Into<PathBuf>>(proc_root: P, sys_root: S) -> Self {
+        CpuSysfs {
+            proc_root: proc_root.into(),
+            sys_root: sys_root.into(),
+        }
+    }
+
+    pub fn cpu_info(&self) -> Result<CpuInfo, Box<dyn Error>> {
+        let cpuinfo = std::fs::read_to_string(self.proc_root.join("cpuinfo"))?;
+        let entries = parse_cpuinfo(&cpuinfo);
+        let first = entries.first().ok_or("/proc/cpuinfo 中没有 CPU")?;
+
+        let (sockets, physical_cores, threads) = self.topology(&entries);
+        let vendor = cpu_vendor(first);
+
+        let mut ml_flags: Vec<String> = vec![];
+        let flags = first.get("flags").or_else(|| first.get("Features"));
+        for flag in flags.map(|flags| flags.split_whitespace()).into_iter().flatten() {
+            if ML_FLAGS.contains(&flag) && !ml_flags.iter().any(|known| known == flag) {
+                ml_flags.push(flag.to_string());
+            }
+        }
+
+        let frequencies = self.frequencies(&entries);
+        let max_mhz = frequencies.iter().filter_map(|core| core.max_mhz).max();
+        let cur_mhz = frequencies.iter().filter_map(|core| core.cur_mhz).max();
+
+        let cpu0 = self.sys_root.join("cpu0");
+        Ok(CpuInfo {
+            uuid: "".to_string(),
+            name: cpu_name(first, &vendor),
+            num: sockets,
+            speed: legacy_speed(max_mhz.or(cur_mhz)),
+            core_num: physical_cores / sockets.max(1),
+            sockets,
+            physical_cores,
+            threads,
+            family: first.get("cpu family").and_then(|value| parse_number(value)),
+            model: first.get("model").or_else(|| first.get("CPU part")).and_then(|value| parse_number(value)),
+            stepping: first.get("stepping").or_else(|| first.get("CPU revision")).and_then(|value| parse_number(value)),
+            microcode: first.get("microcode").cloned(),
+            l1d_cache_bytes: cache_size(&cpu0, 1, "Data"),
+            l1i_cache_bytes: cache_size(&cpu0, 1, "Instruction"),
+            l2_cache_bytes: cache_size(&cpu0, 2, "Unified"),
+            l3_cache_bytes: cache_size(&cpu0, 3, "Unified"),
+            vendor,
+            ml_flags,
+            cur_mhz,
+            min_mhz: frequencies.iter().filter_map(|core| core.min_mhz).min(),
+            max_mhz,
+            governor: frequencies.iter().find_map(|core| core.governor.clone()),
+            core_frequencies: frequencies,
+        })
+    }
+
+    /// 每个逻辑 CPU 的频率，优先读 cpufreq（单位 kHz），
+    /// 虚拟机里通常没有 cpufreq，此时当前频率取 /proc/cpuinfo 的 cpu MHz
+    pub fn frequencies(&self, entries: &[CpuinfoEntry]) -> Vec<CoreFrequency> {
+        let mut frequencies: Vec<CoreFrequency> = sysfs::numbered_entries(&self.sys_root, "cpu")
+            .into_iter()
+            .filter(|(_, cpu)| cpu.join("cpufreq").exists())
+            .map(|(number, cpu)| {
+                let cpufreq = cpu.join("cpufreq");
+                CoreFrequency {
+                    cpu: number,
+                    cur_mhz: sysfs::read_u64(cpufreq.join("scaling_cur_freq")).map(khz_to_mhz),
+                    min_mhz: sysfs::read_u64(cpufreq.join("cpuinfo_min_freq")).map(khz_to_mhz),
+                    max_mhz: sysfs::read_u64(cpufreq.join("cpuinfo_max_freq")).map(khz_to_mhz),
+                    governor: sysfs::read_string(cpufreq.join("scaling_governor")),
+                }
+            })
+            .collect();
+
+        for entry in entries {
+            let cpu = match entry.get("processor").and_then(|value| value.parse::<u32>().ok()) {
+                Some(cpu) => cpu,
+                None => continue,
+            };
+            let cur_mhz = entry.get("cpu MHz").and_then(|value| value.parse::<f64>().ok()).map(|mhz| mhz.round() as u32);
+            match frequencies.iter_mut().find(|core| core.cpu == cpu) {
+                Some(core) => core.cur_mhz = core.cur_mhz.or(cur_mhz),
+                None if cur_mhz.is_some() => frequencies.push(CoreFrequency {
+                    cpu,
+                    cur_mhz,
+                    ..Default::default()
+                }),
+                None => {}
+            }
+        }
+
+        frequencies.sort_by_key(|core| core.cpu);
+        frequencies
+    }
+
+    /// 返回 (插槽数, 物理核心数, 逻辑线程数)
+    /// 优先使用 sysfs 的 topology，ARM 的 /proc/cpuinfo 没有 physical id 和 core id
+    fn topology(&self, entries: &[CpuinfoEntry]) -> (u32, u32, u32) {
+        let cpus = sysfs::numbered_entries(&self.sys_root, "cpu");
+        let ids: Vec<(u64, u64)> = cpus
+            .iter()
+            .filter_map(|(_, cpu)| {
+                let package = sysfs::read_u64(cpu.join("topology/physical_package_id"))?;
+                let core = sysfs::read_u64(cpu.join("topology/core_id"))?;
+                Some((package, core))
+            })
+            .collect();
+
+        let ids = if ids.is_empty() {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let package = entry.get("physical id")?.parse::<u64>().ok()?;
+                    let core = entry.get("core id")?.parse::<u64>().ok()?;
+                    Some((package, core))
+                })
+                .collect()
+        } else {
+            ids
+        };
+
+        let threads = entries.len() as u32;
+        if ids.is_empty() {
+            return (1, threads, threads);
+        }
+
+        let sockets = ids.iter().map(|(package, _)| *package).collect::<HashSet<_>>().len() as u32;
+        let cores = ids.iter().collect::<HashSet<_>>().len() as u32;
+        (sockets, cores, threads)
+    }
+}
+
+/// 按空行切分 /proc/cpuinfo，每个逻辑 CPU 一段；
+/// ARM 的 Hardware 等全局字段单独成段，不算作 CPU
+pub fn parse_cpuinfo(text: &str) -> Vec<CpuinfoEntry> {
+    let mut entries = vec![];
+    let mut entry = CpuinfoEntry::new();
+
+    for line in text.lines().chain(std::iter::once("")) {
+        match line.split_once(':') {
+            Some((key, value)) => {
+                entry.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            None if line.trim().is_empty() => {
+                if entry.contains_key("processor") {
+                    entries.push(std::mem::take(&mut entry));
+                } else {
+                    entry.clear();
+                }
+            }
+            None => {}
+        }
+    }
+
+    entries
+}
+
+/// x86 直接取 vendor_id，ARM 按 CPU implementer 查表
+fn cpu_vendor(entry: &CpuinfoEntry) -> String {
+    if let Some(vendor) = entry.get("vendor_id") {
+        return vendor.clone();
+    }
+
+    let implementer = entry.get("CPU implementer").and_then(|value| parse_number(value));
+    match implementer {
+        Some(0x41) => "ARM",
+        Some(0x42) => "Broadcom",
+        Some(0x46) => "Fujitsu",
+        Some(0x48) => "HiSilicon",
+        Some(0x4e) => "NVIDIA",
+        Some(0x51) => "Qualcomm",
+        Some(0x61) => "Apple",
+        Some(0xc0) => "Ampere",
+        _ => "",
+    }
+    .to_string()
+}
+
+/// x86 取 model name，ARM 按 CPU part 查常见的服务器核心
+fn cpu_name(entry: &CpuinfoEntry, vendor: &str) -> String {
+    if let Some(name) = entry.get("model name") {
+        return name.clone();
+    }
+
+    let part = entry.get("CPU part").and_then(|value| parse_number(value));
+    let name = match (vendor, part) {
+        ("ARM", Some(0xd08)) => "Cortex-A72",
+        ("ARM", Some(0xd0c)) => "Neoverse-N1",
+        ("ARM", Some(0xd40)) => "Neoverse-V1",
+        ("ARM", Some(0xd49)) => "Neoverse-N2",
+        ("ARM", Some(0xd4f)) => "Neoverse-V2",
+        ("HiSilicon", Some(0xd01)) => "Kunpeng-920",
+        ("Ampere", Some(0xac3)) => "Ampere-1",
+        _ => "",
+    };
+    match (name, part) {
+        ("", Some(part)) => format!("{} 0x{:03x}", vendor, part).trim().to_string(),
+        _ => name.to_string(),
+    }
+}
+
+fn khz_to_mhz(khz: u64) -> u32 {
+    (khz / 1000) as u32
+}
+
+/// 旧的 speed 字段，沿用 lscpu 时代 MHz × 1000 的数值
+pub(crate) fn legacy_speed(mhz: Option<u32>) -> u64 {
+    mhz.map(|mhz| mhz as u64 * 1000).unwrap_or(0)
+}
+
+/// 十进制或 0x 开头的十六进制
+fn parse_number(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// cpu0 看到的某一级缓存大小，即单个缓存实例的大小
+fn cache_size(cpu: &Path, level: u64, kind: &str) -> Option<u64> {
+    sysfs::numbered_entries(cpu.join("cache"), "index")
+        .into_iter()
+        .find(|(_, index)| {
+            sysfs::read_u64(index.join("level")) == Some(level)
+                && sysfs::read_string(index.join("type")).as_deref() == Some(kind)
+        })
+        .and_then(|(_, index)| parse_cache_size(&sysfs::read_string(index.join("size"))?))
+}
+
+/// sysfs 的缓存大小，如 "48K"、"2048K"、"32M"
+fn parse_cache_size(value: &str) -> Option<u64> {
+    let (number, scale) = match value.chars().last()? {
+        'K' => (&value[..value.len() - 1], 1 << 10),
+        'M' => (&value[..value.len() - 1], 1 << 20),
+        'G' => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    Some(number.parse::<u64>().ok()? * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Xeon Platinum 8480+ 虚拟机，2 核 4 线程
+    const INTEL: &str = "\
+processor	: 0
+vendor_id	: GenuineIntel
+cpu family	: 6
+model		: 143
+model name	: Intel(R) Xeon(R) Platinum 8480+
+stepping	: 8
+microcode	: 0x2b000461
+cpu MHz		: 2000.000
+cache size	: 107520 KB
+physical id	: 0
+siblings	: 4
+core id		: 0
+cpu cores	: 2
+apicid		: 0
+initial apicid	: 0
+fpu		: yes
+fpu_exception	: yes
+cpuid level	: 32
+wp		: yes
+flags		: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush dts acpi mmx fxsr sse sse2 ss ht tm pbe syscall nx pdpe1gb rdtscp lm constant_tsc pni pclmulqdq fma cx16 sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand lahf_lm abm fsgsbase bmi1 avx2 smep bmi2 erms avx512f avx512dq rdseed adx smap avx512ifma clflushopt avx512cd sha_ni avx512bw avx512vl avx512_bf16 avx512vbmi avx512_vnni avx512_bitalg avx512_vpopcntdq avx512_fp16 amx_bf16 amx_tile amx_int8
+bugs		: spectre_v1 spectre_v2 spec_store_bypass swapgs eibrs_pbrsb
+bogomips	: 4000.00
+clflush size	: 64
+cache_alignment	: 64
+address sizes	: 46 bits physical, 57 bits virtual
+power management:
+
+processor	: 1
+vendor_id	: GenuineIntel
+cpu family	: 6
+model		: 143
+model name	: Intel(R) Xeon(R) Platinum 8480+
+stepping	: 8
+microcode	: 0x2b000461
+cpu MHz		: 3800.012
+cache size	: 107520 KB
+physical id	: 0
+siblings	: 4
+core id		: 0
+cpu cores	: 2
+apicid		: 1
+initial apicid	: 1
+fpu		: yes
+fpu_exception	: yes
+cpuid level	: 32
+wp		: yes
+flags		: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush dts acpi mmx fxsr sse sse2 ss ht tm pbe syscall nx pdpe1gb rdtscp lm constant_tsc pni pclmulqdq fma cx16 sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand lahf_lm abm fsgsbase bmi1 avx2 smep bmi2 erms avx512f avx512dq rdseed adx smap avx512ifma clflushopt avx512cd sha_ni avx512bw avx512vl avx512_bf16 avx512vbmi avx512_vnni avx512_bitalg avx512_vpopcntdq avx512_fp16 amx_bf16 amx_tile amx_int8
+bugs		: spectre_v1 spectre_v2 spec_store_bypass swapgs eibrs_pbrsb
+bogomips	: 4000.00
+clflush size	: 64
+cache_alignment	: 64
+address sizes	: 46 bits physical, 57 bits virtual
+power management:
+
+processor	: 2
+vendor_id	: GenuineIntel
+cpu family	: 6
+model		: 143
+model name	: Intel(R) Xeon(R) Platinum 8480+
+stepping	: 8
+microcode	: 0x2b000461
+cpu MHz		: 2000.000
+cache size	: 107520 KB
+physical id	: 0
+siblings	: 4
+core id		: 1
+cpu cores	: 2
+apicid		: 2
+initial apicid	: 2
+fpu		: yes
+fpu_exception	: yes
+cpuid level	: 32
+wp		: yes
+flags		: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush dts acpi mmx fxsr sse sse2 ss ht tm pbe syscall nx pdpe1gb rdtscp lm constant_tsc pni pclmulqdq fma cx16 sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand lahf_lm abm fsgsbase bmi1 avx2 smep bmi2 erms avx512f avx512dq rdseed adx smap avx512ifma clflushopt avx512cd sha_ni avx512bw avx512vl avx512_bf16 avx512vbmi avx512_vnni avx512_bitalg avx512_vpopcntdq avx512_fp16 amx_bf16 amx_tile amx_int8
+bugs		: spectre_v1 spectre_v2 spec_store_bypass swapgs eibrs_pbrsb
+bogomips	: 4000.00
+clflush size	: 64
+cache_alignment	: 64
+address sizes	: 46 bits physical, 57 bits virtual
+power management:
+
+processor	: 3
+vendor_id	: GenuineIntel
+cpu family	: 6
+model		: 143
+model name	: Intel(R) Xeon(R) Platinum 8480+
+stepping	: 8
+microcode	: 0x2b000461
+cpu MHz		: 800.000
+cache size	: 107520 KB
+physical id	: 0
+siblings	: 4
+core id		: 1
+cpu cores	: 2
+apicid		: 3
+initial apicid	: 3
+fpu		: yes
+fpu_exception	: yes
+cpuid level	: 32
+wp		: yes
+flags		: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush dts acpi mmx fxsr sse sse2 ss ht tm pbe syscall nx pdpe1gb rdtscp lm constant_tsc pni pclmulqdq fma cx16 sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand lahf_lm abm fsgsbase bmi1 avx2 smep bmi2 erms avx512f avx512dq rdseed adx smap avx512ifma clflushopt avx512cd sha_ni avx512bw avx512vl avx512_bf16 avx512vbmi avx512_vnni avx512_bitalg avx512_vpopcntdq avx512_fp16 amx_bf16 amx_tile amx_int8
+bugs		: spectre_v1 spectre_v2 spec_store_bypass swapgs eibrs_pbrsb
+bogomips	: 4000.00
+clflush size	: 64
+cache_alignment	: 64
+address sizes	: 46 bits physical, 57 bits virtual
+power management:
+
+";
+
+    // EPYC 7763 云主机，每个 vCPU 一个插槽
+    const AMD: &str = "\
+processor	: 0
+vendor_id	: AuthenticAMD
+cpu family	: 25
+model		: 1
+model name	: AMD EPYC 7763 64-Core Processor
+stepping	: 1
+microcode	: 0xa0011d1
+cpu MHz		: 2445.406
+cache size	: 512 KB
+physical id	: 0
+siblings	: 1
+core id		: 0
+cpu cores	: 1
+apicid		: 0
+initial apicid	: 0
+fpu		: yes
+fpu_exception	: yes
+cpuid level	: 16
+wp		: yes
+flags		: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush mmx fxsr sse sse2 syscall nx mmxext fxsr_opt pdpe1gb rdtscp lm rep_good nopl cpuid extd_apicid tsc_known_freq pni pclmulqdq ssse3 fma cx16 sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand hypervisor lahf_lm cmp_legacy abm sse4a misalignsse 3dnowprefetch osvw topoext invpcid_single fsgsbase bmi1 avx2 smep bmi2 erms invpcid rdseed adx smap clflushopt clwb sha_ni xsaveopt xsavec xgetbv1 xsaves clzero xsaveerptr wbnoinvd arat npt nrip_save umip vaes vpclmulqdq rdpid fsrm
+bugs		: sysret_ss_attrs null_seg spectre_v1 spectre_v2 spec_store_bypass srso
+bogomips	: 4890.81
+TLB size	: 2560 4K pages
+clflush size	: 64
+cache_alignment	: 64
+address sizes	: 48 bits physical, 48 bits virtual
+power management:
+
+processor	: 1
+vendor_id	: AuthenticAMD
+cpu family	: 25
+model		: 1
+model name	: AMD EPYC 7763 64-Core Processor
+stepping	: 1
+microcode	: 0xa0011d1
+cpu MHz		: 2445.406
+cache size	: 512 KB
+physical id	: 1
+siblings	: 1
+core id		: 0
+cpu cores	: 1
+apicid		: 2
+initial apicid	: 2
+fpu		: yes
+fpu_exception	: yes
+cpuid level	: 16
+wp		: yes
+flags		: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush mmx fxsr sse sse2 syscall nx mmxext fxsr_opt pdpe1gb rdtscp lm rep_good nopl cpuid extd_apicid tsc_known_freq pni pclmulqdq ssse3 fma cx16 sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand hypervisor lahf_lm cmp_legacy abm sse4a misalignsse 3dnowprefetch osvw topoext invpcid_single fsgsbase bmi1 avx2 smep bmi2 erms invpcid rdseed adx smap clflushopt clwb sha_ni xsaveopt xsavec xgetbv1 xsaves clzero xsaveerptr wbnoinvd arat npt nrip_save umip vaes vpclmulqdq rdpid fsrm
+bugs		: sysret_ss_attrs null_seg spectre_v1 spectre_v2 spec_store_bypass srso
+bogomips	: 4890.81
+TLB size	: 2560 4K pages
+clflush size	: 64
+cache_alignment	: 64
+address sizes	: 48 bits physical, 48 bits virtual
+power management:
+
+";
+
+    // 鲲鹏 920，没有 model name、physical id 和 cpu MHz
+    const ARM: &str = "\
+processor	: 0
+BogoMIPS	: 200.00
+Features	: fp asimd evtstrm aes pmull sha1 sha2 crc32 atomics fphp asimdhp cpuid asimdrdm jscvt fcma dcpop asimddp asimdfhm ssbs
+CPU implementer	: 0x48
+CPU architecture: 8
+CPU variant	: 0x1
+CPU part	: 0xd01
+CPU revision	: 0
+
+processor	: 1
+BogoMIPS	: 200.00
+Features	: fp asimd evtstrm aes pmull sha1 sha2 crc32 atomics fphp asimdhp cpuid asimdrdm jscvt fcma dcpop asimddp asimdfhm ssbs
+CPU implementer	: 0x48
+CPU architecture: 8
+CPU variant	: 0x1
+CPU part	: 0xd01
+CPU revision	: 0
+
+processor	: 2
+BogoMIPS	: 200.00
+Features	: fp asimd evtstrm aes pmull sha1 sha2 crc32 atomics fphp asimdhp cpuid asimdrdm jscvt fcma dcpop asimddp asimdfhm ssbs
+CPU implementer	: 0x48
+CPU architecture: 8
+CPU variant	: 0x1
+CPU part	: 0xd01
+CPU revision	: 0
+
+processor	: 3
+BogoMIPS	: 200.00
+Features	: fp asimd evtstrm aes pmull sha1 sha2 crc32 atomics fphp asimdhp cpuid asimdrdm jscvt fcma dcpop asimddp asimdfhm ssbs
+CPU implementer	: 0x48
+CPU architecture: 8
+CPU variant	: 0x1
+CPU part	: 0xd01
+CPU revision	: 0
+
+";
+
+    /// 只有 /proc/cpuinfo、没有 sysfs 的目录
+    fn cpu_info(cpuinfo: &str) -> CpuInfo {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("cpuinfo"), cpuinfo).unwrap();
+        CpuSysfs::with_root(dir.path(), dir.path().join("sys")).cpu_info().unwrap()
+    }
+
+    fn write(path: &Path, data: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn parses_intel_cpuinfo() {
+        let cpu = cpu_info(INTEL);
+        assert_eq!(cpu.vendor, "GenuineIntel");
+        assert_eq!(cpu.name, "Intel(R) Xeon(R) Platinum 8480+");
+        assert_eq!((cpu.sockets, cpu.physical_cores, cpu.threads), (1, 2, 4));
+        assert_eq!((cpu.num, cpu.core_num), (1, 2));
+        assert_eq!((cpu.family, cpu.model, cpu.stepping), (Some(6), Some(143), Some(8)));
+        assert_eq!(cpu.microcode.as_deref(), Some("0x2b000461"));
+        assert_eq!(cpu.ml_flags, vec![
+            "fma", "avx", "f16c", "avx2", "avx512f", "avx512bw", "avx512vl", "avx512_bf16", "avx512_vnni",
+            "avx512_fp16", "amx_bf16", "amx_tile", "amx_int8",
+        ]);
+        // 没有 cpufreq 时取 cpu MHz 的最大值
+        assert_eq!(cpu.cur_mhz, Some(3800));
+        assert_eq!(cpu.max_mhz, None);
+        assert_eq!(cpu.speed, 3_800_000);
+        assert_eq!(cpu.core_frequencies.len(), 4);
+        assert_eq!(cpu.core_frequencies[3].cur_mhz, Some(800));
+    }
+
+    #[test]
+    fn parses_amd_cpuinfo() {
+        let cpu = cpu_info(AMD);
+        assert_eq!(cpu.vendor, "AuthenticAMD");
+        assert_eq!(cpu.name, "AMD EPYC 7763 64-Core Processor");
+        assert_eq!((cpu.sockets, cpu.physical_cores, cpu.threads), (2, 2, 2));
+        assert_eq!(cpu.core_num, 1);
+        assert_eq!((cpu.family, cpu.model, cpu.stepping), (Some(25), Some(1), Some(1)));
+        assert_eq!(cpu.ml_flags, vec!["fma", "avx", "f16c", "avx2"]);
+        assert_eq!(cpu.cur_mhz, Some(2445));
+    }
+
+    #[test]
+    fn parses_arm_cpuinfo() {
+        let cpu = cpu_info(ARM);
+        assert_eq!(cpu.vendor, "HiSilicon");
+        assert_eq!(cpu.name, "Kunpeng-920");
+        // 没有 physical id 和 core id 时按单插槽、每个逻辑 CPU 一个核心处理
+        assert_eq!((cpu.sockets, cpu.physical_cores, cpu.threads), (1, 4, 4));
+        assert_eq!((cpu.family, cpu.model, cpu.stepping), (None, Some(0xd01), Some(0)));
+        assert_eq!(cpu.ml_flags, vec!["asimd", "asimdhp", "asimddp"]);
+        assert_eq!(cpu.cur_mhz, None);
+        assert_eq!(cpu.speed, 0);
+        assert!(cpu.core_frequencies.is_empty());
+    }
+
+    #[test]
+    fn skips_global_sections() {
+        // 树莓派等设备在末尾有 Hardware、Serial 等全局字段
+        let text = "processor\t: 0\nCPU implementer\t: 0x41\nCPU part\t: 0xd08\n\n\
+Hardware\t: BCM2835\nRevision\t: c03111\nSerial\t\t: 10000000a1b2c3d4\nModel\t\t: Raspberry Pi 4 Model B Rev 1.1\n";
+        let entries = parse_cpuinfo(text);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(cpu_name(&entries[0], &cpu_vendor(&entries[0])), "Cortex-A72");
+        assert!(parse_cpuinfo("").is_empty());
+    }
+
+    #[test]
+    fn sysfs_topology_and_frequency_win() {
+        let dir = tempfile::tempdir().unwrap();
+        let sys = dir.path().join("sys");
+        fs::write(dir.path().join("cpuinfo"), ARM).unwrap();
+        for cpu in 0..4 {
+            let root = sys.join(format!("cpu{}", cpu));
+            write(&root.join("topology/physical_package_id"), &format!("{}\n", cpu / 2));
+            write(&root.join("topology/core_id"), &format!("{}\n", cpu % 2));
+            write(&root.join("cpufreq/scaling_cur_freq"), "2600000\n");
+            write(&root.join("cpufreq/cpuinfo_min_freq"), "200000\n");
+            write(&root.join("cpufreq/cpuinfo_max_freq"), "2600000\n");
+            write(&root.join("cpufreq/scaling_governor"), "performance\n");
+        }
+        write(&sys.join("cpu0/cache/index0/level"), "1\n");
+        write(&sys.join("cpu0/cache/index0/type"), "Data\n");
+        write(&sys.join("cpu0/cache/index0/size"), "64K\n");
+        write(&sys.join("cpu0/cache/index2/level"), "3\n");
+        write(&sys.join("cpu0/cache/index2/type"), "Unified\n");
+        write(&sys.join("cpu0/cache/index2/size"), "32768K\n");
+
+        let cpu = CpuSysfs::with_root(dir.path(), &sys).cpu_info().unwrap();
+        assert_eq!((cpu.sockets, cpu.physical_cores, cpu.threads), (2, 4, 4));
+        assert_eq!((cpu.min_mhz, cpu.max_mhz, cpu.cur_mhz), (Some(200), Some(2600), Some(2600)));
+        assert_eq!(cpu.governor.as_deref(), Some("performance"));
+        assert_eq!(cpu.l1d_cache_bytes, Some(64 << 10));
+        assert_eq!(cpu.l3_cache_bytes, Some(32 << 20));
+        assert_eq!(cpu.l2_cache_bytes, None);
+    }
+}
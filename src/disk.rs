@@ -0,0 +1,580 @@
+//! 从 /sys/block 读取物理磁盘的型号、序列号、转速类型和接口，
+//! 并根据 /proc/self/mountinfo 区分真实挂载、虚拟文件系统和重复的 bind mount
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::DiskInfo;
+use crate::sysfs;
+
+/// 默认忽略的文件系统类型，都是内存或内核提供的伪文件系统，以及容器的 overlay、snap 的 squashfs
+const IGNORED_FS_TYPES: &[&str] = &[
+    "tmpfs", "devtmpfs", "ramfs", "overlay", "squashfs", "proc", "sysfs", "cgroup", "cgroup2",
+    "devpts", "mqueue", "hugetlbfs", "debugfs", "tracefs", "securityfs", "pstore", "bpf",
+    "configfs", "fusectl", "autofs", "nsfs", "efivarfs", "binfmt_misc", "rpc_pipefs",
+    "selinuxfs", "fuse.lxcfs", "fuse.snapfuse", "iso9660",
+];
+
+/// 挂载点分类，写入 DiskInfo.mount_kind
+pub const MOUNT_REAL: &str = "real";
+pub const MOUNT_VIRTUAL: &str = "virtual";
+pub const MOUNT_BIND: &str = "bind";
+
+/// 磁盘统计的配置
+#[derive(Debug, Clone)]
+pub struct DiskConfig {
+    /// 这些文件系统类型的挂载点归为 virtual，不计入容量
+    pub ignored_fs_types: Vec<String>,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        DiskConfig {
+            ignored_fs_types: IGNORED_FS_TYPES.iter().map(|fs_type| fs_type.to_string()).collect(),
+        }
+    }
+}
+
+impl DiskConfig {
+    /// 读取 home 目录下的 ignored_fs_types.dat，空白分隔的文件系统类型，文件不存在时使用默认列表
+    pub fn load() -> Self {
+        let path = match wei_env::home_dir() {
+            Ok(home_dir) => format!("{}ignored_fs_types.dat", home_dir),
+            Err(_) => return DiskConfig::default(),
+        };
+        match std::fs::read_to_string(path) {
+            Ok(data) if !data.trim().is_empty() => DiskConfig {
+                ignored_fs_types: data.split_whitespace().map(|fs_type| fs_type.to_string()).collect(),
+            },
+            _ => DiskConfig::default(),
+        }
+    }
+
+    pub fn is_ignored(&self, fs_type: &str) -> bool {
+        self.ignored_fs_types.iter().any(|ignored| ignored == fs_type)
+    }
+}
+
+/// /proc/self/mountinfo 中的一行
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MountEntry {
+    pub major_minor: String,
+    /// 挂载的是文件系统中的哪个目录，bind mount 时不是 "/"
+    pub root: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    pub source: String,
+}
+
+/// 解析 mountinfo，格式为
+/// 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+pub fn parse_mountinfo(data: &str) -> Vec<MountEntry> {
+    data.lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once(" - ")?;
+            let left: Vec<&str> = left.split_whitespace().collect();
+            let mut right = right.split_whitespace();
+            if left.len() < 5 {
+                return None;
+            }
+            Some(MountEntry {
+                major_minor: left[2].to_string(),
+                root: unescape(left[3]),
+                mount_point: unescape(left[4]),
+                fs_type: right.next()?.to_string(),
+                source: right.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+/// mountinfo 中空格等字符写成 \040 这样的八进制转义
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 4 <= bytes.len() && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let code = (bytes[i + 1] - b'0') * 64 + (bytes[i + 2] - b'0') * 8 + (bytes[i + 3] - b'0');
+            result.push(code);
+            i += 4;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// 给每个挂载点填上 major_minor 和 mount_kind：
+/// 文件系统类型在忽略列表中的为 virtual；同一个设备号挂载多次时，
+/// 挂载根目录为 "/" 且路径最短的一条为 real，其余为 bind。
+/// 容器中只有子目录被 bind 进来的设备，取其中一条作为 real，容量仍然只算一次
+pub fn classify_mounts(disks: &mut [DiskInfo], mounts: &[MountEntry], config: &DiskConfig) {
+    for disk in disks.iter_mut() {
+        let mount = mounts.iter().rev().find(|mount| mount.mount_point == disk.mount_point);
+        if let Some(mount) = mount {
+            disk.major_minor = mount.major_minor.clone();
+            if disk.file_system.is_empty() {
+                disk.file_system = mount.fs_type.clone();
+            }
+        }
+        let root = mount.map(|mount| mount.root.as_str()).unwrap_or("/");
+
+        disk.mount_kind = if config.is_ignored(&disk.file_system) {
+            MOUNT_VIRTUAL.to_string()
+        } else if root != "/" {
+            MOUNT_BIND.to_string()
+        } else {
+            MOUNT_REAL.to_string()
+        };
+    }
+
+    // 同一设备只保留一个 real
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut order: Vec<usize> = (0..disks.len()).collect();
+    order.sort_by_key(|&i| (disks[i].mount_kind != MOUNT_REAL, disks[i].mount_point.len()));
+    for i in order {
+        if disks[i].mount_kind == MOUNT_VIRTUAL || disks[i].major_minor.is_empty() {
+            continue;
+        }
+        disks[i].mount_kind = if seen.insert(disks[i].major_minor.clone()) {
+            MOUNT_REAL.to_string()
+        } else {
+            MOUNT_BIND.to_string()
+        };
+    }
+}
+
+/// 按物理磁盘汇总 real 挂载点的容量，同一块盘上有多个分区时相加；
+/// md 阵列、跨多块盘的 LVM 的容量无法拆到各块盘上，disk_of 返回 md0、dm-0 等设备名，不计入任何物理磁盘
+pub fn aggregate_storage(physical_disks: &mut [PhysicalDisk], disks: &[DiskInfo]) {
+    for physical_disk in physical_disks.iter_mut() {
+        let mounts: Vec<&DiskInfo> = disks
+            .iter()
+            .filter(|disk| disk.mount_kind == MOUNT_REAL && disk.disk == physical_disk.name)
+            .collect();
+        if mounts.is_empty() {
+            continue;
+        }
+        physical_disk.usable_bytes = Some(mounts.iter().map(|disk| disk.total_space).sum());
+        physical_disk.available_bytes = Some(mounts.iter().map(|disk| disk.available_space).sum());
+    }
+}
+
+/// 一块物理磁盘，对应 /sys/block 下的一个设备
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PhysicalDisk {
+    /// 内核设备名，如 nvme0n1、sda、vda
+    pub name: String,
+    pub model: String,
+    pub serial: Option<String>,
+    /// 机械盘为 true，虚拟磁盘的值不一定可靠
+    pub rotational: Option<bool>,
+    /// nvme、sata、sas、usb、virtio、mmc，识别不出时为空
+    pub transport: String,
+    /// 单位字节
+    pub size_bytes: u64,
+    /// 设备号，如 "259:0"
+    pub major_minor: String,
+    /// 该盘上所有真实挂载点的文件系统容量之和，没有挂载时为 None；
+    /// 作为 md 阵列或多盘 LVM 成员的盘不计入阵列上的容量
+    pub usable_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+}
+
+impl PhysicalDisk {
+    /// 与 Windows Get-PhysicalDisk 的 MediaType 一致：SSD、HDD 或 Unspecified
+    pub fn media_type(&self) -> &'static str {
+        match self.rotational {
+            Some(true) => "HDD",
+            Some(false) => "SSD",
+            None => "Unspecified",
+        }
+    }
+}
+
+/// 通过 /sys/block 获取物理磁盘
+pub struct BlockSysfs {
+    root: PathBuf,
+    class_root: PathBuf,
+}
+
+impl Default for BlockSysfs {
+    fn default() -> Self {
+        BlockSysfs::with_root("/sys/block", "/sys/class/block")
+    }
+}
+
+impl BlockSysfs {
+    /// 指定 /sys/block 和 /sys/class/block 的位置，测试时可以指向伪造的目录树
+    pub fn with_root<P: Into<PathBuf>, C: Into<PathBuf>>(root: P, class_root: C) -> Self {
+        BlockSysfs {
+            root: root.into(),
+            class_root: class_root.into(),
+        }
+    }
+
+    /// 有 device 链接的块设备，跳过 loop、zram、dm 等虚拟设备，
+    /// 以及光驱（sr*）和 U 盘、读卡器等可移动设备
+    pub fn physical_disks(&self) -> Vec<PhysicalDisk> {
+        let mut names: Vec<String> = match std::fs::read_dir(&self.root) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().join("device").exists())
+                .filter(|entry| sysfs::read_u64(entry.path().join("removable")) != Some(1))
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| !name.starts_with("sr"))
+                .collect(),
+            Err(err) => {
+                info!("读取 {} 失败: {}", self.root.display(), err);
+                return vec![];
+            }
+        };
+        names.sort();
+
+        names.iter().map(|name| self.physical_disk(name)).collect()
+    }
+
+    fn physical_disk(&self, name: &str) -> PhysicalDisk {
+        let dir = self.root.join(name);
+        let device = dir.join("device");
+
+        PhysicalDisk {
+            name: name.to_string(),
+            model: sysfs::read_string(device.join("model")).unwrap_or_default(),
+            serial: sysfs::read_string(device.join("serial")).or_else(|| sysfs::read_string(dir.join("serial"))),
+            rotational: sysfs::read_u64(dir.join("queue/rotational")).map(|rotational| rotational == 1),
+            transport: transport(name, &dir),
+            // size 的单位固定是 512 字节的扇区，与实际扇区大小无关
+            size_bytes: sysfs::read_u64(dir.join("size")).unwrap_or(0) * 512,
+            major_minor: sysfs::read_string(dir.join("dev")).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    /// 分区、LVM、RAID 等块设备所在的物理磁盘名，
+    /// 如 /dev/nvme0n1p1 -> nvme0n1，/dev/mapper/vg-root -> dm-0 的下层磁盘；
+    /// 下层有多块盘的 md、dm 设备返回其自身的设备名，如 /dev/md0 -> md0
+    pub fn disk_of(&self, device: &str) -> Option<String> {
+        // /dev/mapper/xxx 是指向 /dev/dm-N 的链接
+        let device = std::fs::canonicalize(device).unwrap_or_else(|_| PathBuf::from(device));
+        let name = device.file_name()?.to_string_lossy().into_owned();
+        self.resolve_disk(&name, 0)
+    }
+
+    fn resolve_disk(&self, name: &str, depth: u32) -> Option<String> {
+        if depth > 8 {
+            return None;
+        }
+
+        let dir = self.root.join(name);
+        if dir.exists() {
+            // dm、md 设备通过 slaves 找到下层设备，有多个下层设备时不归到其中任何一块盘
+            let slaves = entries(&dir.join("slaves"));
+            return match slaves.as_slice() {
+                [slave] => self.resolve_disk(slave, depth + 1),
+                _ => Some(name.to_string()),
+            };
+        }
+
+        // 分区在 /sys/class/block 中，真实路径的上一级是所在的磁盘
+        let path = std::fs::canonicalize(self.class_root.join(name)).ok()?;
+        let parent = path.parent()?.file_name()?.to_string_lossy().into_owned();
+        if self.root.join(&parent).exists() {
+            self.resolve_disk(&parent, depth + 1)
+        } else {
+            None
+        }
+    }
+}
+
+/// 按设备名和 sysfs 中的真实路径判断接口类型
+fn transport(name: &str, dir: &Path) -> String {
+    if name.starts_with("nvme") {
+        return "nvme".to_string();
+    }
+
+    let path = std::fs::canonicalize(dir).unwrap_or_default().to_string_lossy().into_owned();
+    let transport = if path.contains("/usb") {
+        "usb"
+    } else if path.contains("/virtio") {
+        "virtio"
+    } else if path.contains("/ata") {
+        "sata"
+    } else if path.contains("/mmc") {
+        "mmc"
+    } else if path.contains("/end_device-") || path.contains("/sas_") {
+        "sas"
+    } else {
+        ""
+    };
+    transport.to_string()
+}
+
+fn entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => vec![],
+    };
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(path: &Path, data: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    fn block_device(root: &Path, name: &str, removable: &str, rotational: &str, sectors: &str) -> PathBuf {
+        let dir = root.join(name);
+        fs::create_dir_all(dir.join("device")).unwrap();
+        write(&dir.join("removable"), removable);
+        write(&dir.join("queue/rotational"), rotational);
+        write(&dir.join("size"), sectors);
+        dir
+    }
+
+    #[test]
+    fn skips_optical_and_removable_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("block");
+
+        let nvme = block_device(&root, "nvme0n1", "0\n", "0\n", "3750748848\n");
+        write(&nvme.join("device/model"), "SAMSUNG MZQL23T8HCLS-00A07\n");
+        write(&nvme.join("device/serial"), "S64HNE0R123456\n");
+        write(&nvme.join("dev"), "259:0\n");
+        let sda = block_device(&root, "sda", "0\n", "1\n", "31251759104\n");
+        write(&sda.join("device/model"), "ST16000NM001G-2K\n");
+        // 光驱按设备名跳过，不依赖 removable
+        block_device(&root, "sr0", "0\n", "1\n", "2097151\n");
+        block_device(&root, "sdb", "1\n", "1\n", "61440000\n");
+        // loop 设备没有 device 链接
+        write(&root.join("loop0/size"), "0\n");
+
+        let disks = BlockSysfs::with_root(&root, dir.path().join("class")).physical_disks();
+        let names: Vec<&str> = disks.iter().map(|disk| disk.name.as_str()).collect();
+        assert_eq!(names, vec!["nvme0n1", "sda"]);
+
+        assert_eq!(disks[0].model, "SAMSUNG MZQL23T8HCLS-00A07");
+        assert_eq!(disks[0].serial.as_deref(), Some("S64HNE0R123456"));
+        assert_eq!(disks[0].transport, "nvme");
+        assert_eq!(disks[0].media_type(), "SSD");
+        assert_eq!(disks[0].size_bytes, 3750748848 * 512);
+        assert_eq!(disks[0].major_minor, "259:0");
+        assert_eq!(disks[1].media_type(), "HDD");
+        assert_eq!(disks[1].serial, None);
+    }
+
+    #[test]
+    fn missing_block_directory_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(BlockSysfs::with_root(dir.path().join("block"), dir.path()).physical_disks().is_empty());
+    }
+
+    // Docker 宿主机：根分区、EFI 分区、/data 上的 docker 目录和模型目录又被 bind 到别处，
+    // 还有容器的 overlay、snap 的 squashfs 和带空格的挂载点
+    const HOST_MOUNTINFO: &str = "\
+22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw,errors=remount-ro
+23 22 259:1 / /data rw,relatime shared:2 - xfs /dev/nvme1n1p1 rw,attr2,inode64
+24 22 0:5 / /dev rw,nosuid,relatime shared:3 - devtmpfs udev rw,size=263921348k
+25 22 0:25 / /run rw,nosuid,nodev,noexec,relatime shared:5 - tmpfs tmpfs rw,size=52791240k
+26 22 259:3 / /boot/efi rw,relatime shared:6 - vfat /dev/nvme0n1p1 rw,fmask=0077
+27 22 7:0 / /snap/core20/2105 ro,nodev,relatime shared:7 - squashfs /dev/loop0 ro
+28 22 259:1 /docker /var/lib/docker rw,relatime shared:2 - xfs /dev/nvme1n1p1 rw,attr2,inode64
+29 22 259:1 /models /mnt/models rw,relatime shared:2 - xfs /dev/nvme1n1p1 rw,attr2,inode64
+30 23 259:1 /docker /data/docker rw,relatime shared:2 - xfs /dev/nvme1n1p1 rw,attr2,inode64
+31 28 0:50 / /var/lib/docker/overlay2/3f2a9c/merged rw,relatime - overlay overlay rw,lowerdir=/var/lib/docker/overlay2/l/ABC
+32 22 259:4 / /scratch rw,relatime shared:8 - ext4 /dev/nvme1n1p2 rw
+33 22 8:1 / /mnt/backup\\040disk rw,relatime shared:9 - ext4 /dev/sda1 rw
+";
+
+    // 容器里：/data 本身没有挂进来，只有它的子目录
+    const CONTAINER_MOUNTINFO: &str = "\
+500 400 0:60 / / rw,relatime - overlay overlay rw,lowerdir=/var/lib/docker/overlay2/l/XYZ
+501 500 0:63 / /proc rw,nosuid,nodev,noexec,relatime - proc proc rw
+502 500 259:1 /docker/containers/9b1e/hostname /etc/hostname rw,relatime - xfs /dev/nvme1n1p1 rw
+503 500 259:1 /docker/containers/9b1e/hosts /etc/hosts rw,relatime - xfs /dev/nvme1n1p1 rw
+504 500 259:1 /models /models rw,relatime - xfs /dev/nvme1n1p1 rw
+";
+
+    /// sysinfo 列出的一个挂载点
+    fn mount(mount_point: &str, disk: &str, total_space: u64, available_space: u64) -> DiskInfo {
+        DiskInfo {
+            mount_point: mount_point.to_string(),
+            disk: disk.to_string(),
+            total_space,
+            available_space,
+            ..Default::default()
+        }
+    }
+
+    fn kinds(disks: &[DiskInfo]) -> Vec<(&str, &str)> {
+        disks.iter().map(|disk| (disk.mount_point.as_str(), disk.mount_kind.as_str())).collect()
+    }
+
+    #[test]
+    fn parses_mountinfo_lines() {
+        let mounts = parse_mountinfo(HOST_MOUNTINFO);
+        assert_eq!(mounts.len(), 12);
+        assert_eq!(mounts[6], MountEntry {
+            major_minor: "259:1".to_string(),
+            root: "/docker".to_string(),
+            mount_point: "/var/lib/docker".to_string(),
+            fs_type: "xfs".to_string(),
+            source: "/dev/nvme1n1p1".to_string(),
+        });
+        assert_eq!(mounts[11].mount_point, "/mnt/backup disk");
+
+        // 缺少 " - " 分隔符或字段不够的行跳过
+        assert!(parse_mountinfo("22 1 259:2 / / rw\n22 1 259:2 - ext4 /dev/sda1 rw\n").is_empty());
+    }
+
+    #[test]
+    fn unescapes_octal_sequences() {
+        assert_eq!(unescape("/mnt/backup\\040disk"), "/mnt/backup disk");
+        assert_eq!(unescape("/mnt/a\\011b\\134c"), "/mnt/a\tb\\c");
+        // 不是三位八进制时原样保留
+        assert_eq!(unescape("/mnt/a\\09b"), "/mnt/a\\09b");
+        assert_eq!(unescape("/mnt/a\\04"), "/mnt/a\\04");
+    }
+
+    #[test]
+    fn classifies_docker_host_mounts() {
+        let mut disks = vec![
+            mount("/", "nvme0n1", 0, 0),
+            mount("/data", "nvme1n1", 0, 0),
+            mount("/dev", "", 0, 0),
+            mount("/run", "", 0, 0),
+            mount("/boot/efi", "nvme0n1", 0, 0),
+            mount("/snap/core20/2105", "", 0, 0),
+            mount("/var/lib/docker", "nvme1n1", 0, 0),
+            mount("/mnt/models", "nvme1n1", 0, 0),
+            mount("/data/docker", "nvme1n1", 0, 0),
+            mount("/var/lib/docker/overlay2/3f2a9c/merged", "", 0, 0),
+            mount("/scratch", "nvme1n1", 0, 0),
+            mount("/mnt/backup disk", "sda", 0, 0),
+        ];
+        classify_mounts(&mut disks, &parse_mountinfo(HOST_MOUNTINFO), &DiskConfig::default());
+
+        assert_eq!(kinds(&disks), vec![
+            ("/", MOUNT_REAL),
+            ("/data", MOUNT_REAL),
+            ("/dev", MOUNT_VIRTUAL),
+            ("/run", MOUNT_VIRTUAL),
+            ("/boot/efi", MOUNT_REAL),
+            ("/snap/core20/2105", MOUNT_VIRTUAL),
+            ("/var/lib/docker", MOUNT_BIND),
+            ("/mnt/models", MOUNT_BIND),
+            ("/data/docker", MOUNT_BIND),
+            ("/var/lib/docker/overlay2/3f2a9c/merged", MOUNT_VIRTUAL),
+            ("/scratch", MOUNT_REAL),
+            ("/mnt/backup disk", MOUNT_REAL),
+        ]);
+        assert_eq!(disks[1].major_minor, "259:1");
+        assert_eq!(disks[8].major_minor, "259:1");
+        // sysinfo 没给文件系统类型时取 mountinfo 中的
+        assert_eq!(disks[4].file_system, "vfat");
+    }
+
+    #[test]
+    fn promotes_one_subdirectory_bind_in_container() {
+        let mut disks = vec![
+            mount("/", "", 0, 0),
+            mount("/proc", "", 0, 0),
+            mount("/etc/hostname", "nvme1n1", 0, 0),
+            mount("/etc/hosts", "nvme1n1", 0, 0),
+            mount("/models", "nvme1n1", 0, 0),
+        ];
+        classify_mounts(&mut disks, &parse_mountinfo(CONTAINER_MOUNTINFO), &DiskConfig::default());
+
+        assert_eq!(kinds(&disks), vec![
+            ("/", MOUNT_VIRTUAL),
+            ("/proc", MOUNT_VIRTUAL),
+            ("/etc/hostname", MOUNT_BIND),
+            ("/etc/hosts", MOUNT_BIND),
+            ("/models", MOUNT_REAL),
+        ]);
+    }
+
+    #[test]
+    fn aggregates_partitions_and_skips_binds() {
+        let mut disks = vec![
+            mount("/data", "nvme1n1", 2000, 500),
+            mount("/var/lib/docker", "nvme1n1", 2000, 500),
+            mount("/mnt/models", "nvme1n1", 2000, 500),
+            mount("/scratch", "nvme1n1", 1000, 800),
+            mount("/", "nvme0n1", 400, 100),
+        ];
+        classify_mounts(&mut disks, &parse_mountinfo(HOST_MOUNTINFO), &DiskConfig::default());
+
+        let mut physical_disks = vec![
+            PhysicalDisk { name: "nvme0n1".to_string(), ..Default::default() },
+            PhysicalDisk { name: "nvme1n1".to_string(), ..Default::default() },
+            PhysicalDisk { name: "sdb".to_string(), ..Default::default() },
+        ];
+        aggregate_storage(&mut physical_disks, &disks);
+
+        assert_eq!(physical_disks[0].usable_bytes, Some(400));
+        assert_eq!(physical_disks[1].usable_bytes, Some(3000));
+        assert_eq!(physical_disks[1].available_bytes, Some(1300));
+        // 没有挂载点的盘
+        assert_eq!(physical_disks[2].usable_bytes, None);
+    }
+
+    #[test]
+    fn custom_ignored_fs_types() {
+        let config = DiskConfig { ignored_fs_types: vec!["nfs4".to_string(), "overlay".to_string()] };
+        assert!(config.is_ignored("nfs4"));
+        assert!(!config.is_ignored("tmpfs"));
+        assert!(!config.is_ignored("nfs"));
+
+        // tmpfs 不在自定义列表里，按真实挂载统计
+        let mut disks = vec![mount("/run", "", 0, 0), mount("/var/lib/docker/overlay2/3f2a9c/merged", "", 0, 0)];
+        classify_mounts(&mut disks, &parse_mountinfo(HOST_MOUNTINFO), &config);
+        assert_eq!(kinds(&disks), vec![("/run", MOUNT_REAL), ("/var/lib/docker/overlay2/3f2a9c/merged", MOUNT_VIRTUAL)]);
+    }
+
+    #[test]
+    fn md_array_is_not_attributed_to_a_member() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("block");
+        block_device(&root, "sda", "0\n", "1\n", "1000\n");
+        block_device(&root, "sdb", "0\n", "1\n", "1000\n");
+        block_device(&root, "sdc", "0\n", "0\n", "1000\n");
+        // md0 是 sda、sdb 的 RAID1，dm-0 是 md0 上的 LVM，dm-1 只在 sdc 上
+        fs::create_dir_all(root.join("md0/slaves/sda")).unwrap();
+        fs::create_dir_all(root.join("md0/slaves/sdb")).unwrap();
+        fs::create_dir_all(root.join("dm-0/slaves/md0")).unwrap();
+        fs::create_dir_all(root.join("dm-1/slaves/sdc")).unwrap();
+
+        let block = BlockSysfs::with_root(&root, dir.path().join("class"));
+        assert_eq!(block.disk_of("md0").as_deref(), Some("md0"));
+        assert_eq!(block.disk_of("dm-0").as_deref(), Some("md0"));
+        assert_eq!(block.disk_of("dm-1").as_deref(), Some("sdc"));
+
+        let mut physical_disks = block.physical_disks();
+        let names: Vec<&str> = physical_disks.iter().map(|disk| disk.name.as_str()).collect();
+        assert_eq!(names, vec!["sda", "sdb", "sdc"]);
+
+        let disks = vec![
+            DiskInfo { mount_kind: MOUNT_REAL.to_string(), ..mount("/data", "md0", 1000, 600) },
+            DiskInfo { mount_kind: MOUNT_REAL.to_string(), ..mount("/srv", "sdc", 300, 100) },
+        ];
+        aggregate_storage(&mut physical_disks, &disks);
+        assert_eq!(physical_disks[0].usable_bytes, None);
+        assert_eq!(physical_disks[1].usable_bytes, None);
+        assert_eq!(physical_disks[2].usable_bytes, Some(300));
+    }
+}
@@ -0,0 +1,179 @@
+//! 从 /sys/class/dmi/id 读取主板、BIOS 和整机型号
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+
+/// 整机厂商和型号，读不到的字段为空字符串
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SystemInfo {
+    pub sys_vendor: String,
+    pub product_name: String,
+    pub product_version: String,
+    /// 序列号需要 root 才能读取，读不到时为 None
+    pub product_serial: Option<String>,
+    pub board_vendor: String,
+    pub board_name: String,
+    pub board_version: String,
+    pub board_serial: Option<String>,
+    pub bios_vendor: String,
+    pub bios_version: String,
+    pub bios_date: String,
+    /// SMBIOS 机箱类型编号，如 3 为台式机，23 为机架式
+    pub chassis_type: Option<u32>,
+    /// 机箱类型名称，如 "Desktop"、"Rack Mount Chassis"
+    pub chassis: String,
+}
+
+/// 通过 sysfs 的 DMI 目录获取整机信息
+pub struct DmiSysfs {
+    root: PathBuf,
+}
+
+impl Default for DmiSysfs {
+    fn default() -> Self {
+        DmiSysfs::with_root("/sys/class/dmi/id")
+    }
+}
+
+impl DmiSysfs {
+    /// 指定 DMI 目录，测试时可以指向伪造的目录树
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> Self {
+        DmiSysfs { root: root.into() }
+    }
+
+    /// ARM 开发板、部分虚拟机和容器里没有 DMI 目录，此时返回空的整机信息
+    pub fn system_info(&self) -> Result<SystemInfo, Box<dyn Error>> {
+        if !self.root.is_dir() {
+            info!("{} 不存在，跳过整机信息", self.root.display());
+            return Ok(SystemInfo::default());
+        }
+
+        let chassis_type = self.read("chassis_type").parse::<u32>().ok();
+        Ok(SystemInfo {
+            sys_vendor: self.read("sys_vendor"),
+            product_name: self.read("product_name"),
+            product_version: self.read("product_version"),
+            product_serial: self.read_serial("product_serial"),
+            board_vendor: self.read("board_vendor"),
+            board_name: self.read("board_name"),
+            board_version: self.read("board_version"),
+            board_serial: self.read_serial("board_serial"),
+            bios_vendor: self.read("bios_vendor"),
+            bios_version: self.read("bios_version"),
+            bios_date: self.read("bios_date"),
+            chassis: chassis_type.map(chassis_name).unwrap_or_default().to_string(),
+            chassis_type,
+        })
+    }
+
+    fn read(&self, name: &str) -> String {
+        sysfs::read_string(self.root.join(name)).unwrap_or_default()
+    }
+
+    /// 序列号文件权限为 0400，普通用户读取时返回 None 而不是报错
+    fn read_serial(&self, name: &str) -> Option<String> {
+        match std::fs::read_to_string(self.root.join(name)) {
+            Ok(serial) => Some(serial.trim().to_string()).filter(|serial| !serial.is_empty()),
+            Err(err) => {
+                if err.kind() == std::io::ErrorKind::PermissionDenied {
+                    info!("没有权限读取 {}", name);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// SMBIOS 规范中的机箱类型
+fn chassis_name(chassis_type: u32) -> &'static str {
+    match chassis_type {
+        1 => "Other",
+        2 => "Unknown",
+        3 => "Desktop",
+        4 => "Low Profile Desktop",
+        5 => "Pizza Box",
+        6 => "Mini Tower",
+        7 => "Tower",
+        8 => "Portable",
+        9 => "Laptop",
+        10 => "Notebook",
+        11 => "Hand Held",
+        12 => "Docking Station",
+        13 => "All in One",
+        14 => "Sub Notebook",
+        15 => "Space-saving",
+        16 => "Lunch Box",
+        17 => "Main Server Chassis",
+        18 => "Expansion Chassis",
+        19 => "SubChassis",
+        20 => "Bus Expansion Chassis",
+        21 => "Peripheral Chassis",
+        22 => "RAID Chassis",
+        23 => "Rack Mount Chassis",
+        24 => "Sealed-case PC",
+        25 => "Multi-system Chassis",
+        26 => "Compact PCI",
+        27 => "Advanced TCA",
+        28 => "Blade",
+        29 => "Blade Enclosure",
+        30 => "Tablet",
+        31 => "Convertible",
+        32 => "Detachable",
+        33 => "IoT Gateway",
+        34 => "Embedded PC",
+        35 => "Mini PC",
+        36 => "Stick PC",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reads_dmi_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (name, value) in [
+            ("sys_vendor", "Supermicro\n"),
+            ("product_name", "SYS-420GP-TNR\n"),
+            ("product_version", "0123456789\n"),
+            ("product_serial", "A123456789\n"),
+            ("board_vendor", "Supermicro\n"),
+            ("board_name", "X12DPG-OA6\n"),
+            ("board_version", "1.01A\n"),
+            ("board_serial", "\n"),
+            ("bios_vendor", "American Megatrends International, LLC.\n"),
+            ("bios_version", "1.4a\n"),
+            ("bios_date", "06/14/2022\n"),
+            ("chassis_type", "23\n"),
+        ] {
+            fs::write(root.join(name), value).unwrap();
+        }
+
+        let info = DmiSysfs::with_root(root).system_info().unwrap();
+        assert_eq!(info.sys_vendor, "Supermicro");
+        assert_eq!(info.product_name, "SYS-420GP-TNR");
+        assert_eq!(info.product_serial.as_deref(), Some("A123456789"));
+        assert_eq!(info.board_name, "X12DPG-OA6");
+        // 空的序列号视为没有
+        assert_eq!(info.board_serial, None);
+        assert_eq!(info.bios_date, "06/14/2022");
+        assert_eq!(info.chassis_type, Some(23));
+        assert_eq!(info.chassis, "Rack Mount Chassis");
+    }
+
+    #[test]
+    fn missing_dmi_directory_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = DmiSysfs::with_root(dir.path().join("dmi/id")).system_info().unwrap();
+        assert_eq!(info, SystemInfo::default());
+    }
+}
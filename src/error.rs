@@ -0,0 +1,77 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+/// 各个探测项的错误，一个探测项对应一个变体
+#[derive(Debug)]
+pub enum HardwareError {
+    Cpu(String),
+    /// 主板、BIOS 等整机信息
+    System(String),
+    Gpu(String),
+    Memory(String),
+    Numa(String),
+    Disk(String),
+    Network(String),
+    PublicIp(String),
+    /// model / dataset 目录扫描
+    Files { dir: String, cause: String },
+    /// wei-docker 子命令
+    Docker { command: String, cause: String },
+}
+
+impl HardwareError {
+    /// 探测项名称，写入上报数据的 errors 字段
+    pub fn probe(&self) -> &str {
+        match self {
+            HardwareError::Cpu(_) => "cpu",
+            HardwareError::System(_) => "system",
+            HardwareError::Gpu(_) => "gpu",
+            HardwareError::Memory(_) => "memory",
+            HardwareError::Numa(_) => "numa",
+            HardwareError::Disk(_) => "disk",
+            HardwareError::Network(_) => "network",
+            HardwareError::PublicIp(_) => "ip",
+            HardwareError::Files { dir, .. } => dir,
+            HardwareError::Docker { .. } => "docker",
+        }
+    }
+
+    pub fn cause(&self) -> String {
+        match self {
+            HardwareError::Cpu(cause)
+            | HardwareError::System(cause)
+            | HardwareError::Gpu(cause)
+            | HardwareError::Memory(cause)
+            | HardwareError::Numa(cause)
+            | HardwareError::Disk(cause)
+            | HardwareError::Network(cause)
+            | HardwareError::PublicIp(cause) => cause.clone(),
+            HardwareError::Files { cause, .. } => cause.clone(),
+            HardwareError::Docker { command, cause } => format!("{}: {}", command, cause),
+        }
+    }
+}
+
+impl fmt::Display for HardwareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 探测失败: {}", self.probe(), self.cause())
+    }
+}
+
+impl std::error::Error for HardwareError {}
+
+/// 上报数据里的错误条目
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProbeError {
+    pub probe: String,
+    pub error: String,
+}
+
+impl From<&HardwareError> for ProbeError {
+    fn from(err: &HardwareError) -> Self {
+        ProbeError {
+            probe: err.probe().to_string(),
+            error: err.cause(),
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! 由多个硬件标识组合出的机器指纹，uuid 文件被删除或者磁盘被克隆时仍然可以识别同一台机器
+
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+use crate::GpuInfo;
+#[cfg(not(target_os = "windows"))]
+use crate::sysfs;
+
+/// 主板厂商没有填写时常见的占位值，不参与计算
+const PLACEHOLDERS: &[&str] = &[
+    "none",
+    "default string",
+    "to be filled by o.e.m.",
+    "not specified",
+    "not applicable",
+    "system serial number",
+    "0",
+    "00000000-0000-0000-0000-000000000000",
+    "ffffffff-ffff-ffff-ffff-ffffffffffff",
+    "03000200-0400-0500-0006-000700080009",
+    "00:00:00:00:00:00",
+];
+
+/// 读取到的一个标识，只上报名称，不上报原始值
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FingerprintComponent {
+    /// machine_id、product_uuid、board_serial、primary_mac、gpu_uuids
+    pub name: String,
+    #[serde(skip)]
+    pub value: String,
+    /// 是否参与指纹计算；换网卡、换显卡就会变的标识只上报，不参与计算
+    #[serde(default)]
+    pub stable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MachineFingerprint {
+    /// 稳定标识拼接后的 sha256，十六进制；没有稳定标识时退回用易变的标识计算，都没有时为空
+    pub fingerprint: String,
+    pub components: Vec<FingerprintComponent>,
+    /// 当前 uuid 文件中的值
+    pub uuid: String,
+    /// 第一次记录该指纹时的 uuid
+    pub previous_uuid: Option<String>,
+    /// 指纹相同但 uuid 与第一次记录的不同，通常是 uuid 文件被删除重建，或者磁盘被克隆；
+    /// 调用 acknowledge_uuid_change 确认之前会一直报告
+    pub clone_detected: bool,
+}
+
+/// 第一次计算出该指纹时的 uuid，保存在缓存目录中
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct FingerprintRecord {
+    fingerprint: String,
+    uuid: String,
+}
+
+impl MachineFingerprint {
+    /// 按固定顺序拼接稳定标识后计算哈希，占位值和空值会被跳过；
+    /// volatile 中的标识只记录在 components 里，只有一个稳定标识都没有时才参与计算
+    pub fn from_components(stable: Vec<(&str, String)>, volatile: Vec<(&str, String)>) -> Self {
+        let stable = stable.into_iter().map(|(name, value)| (name, value, true));
+        let volatile = volatile.into_iter().map(|(name, value)| (name, value, false));
+        let components: Vec<FingerprintComponent> = stable
+            .chain(volatile)
+            .map(|(name, value, stable)| (name, value.trim().to_string(), stable))
+            .filter(|(_, value, _)| !value.is_empty() && !PLACEHOLDERS.contains(&value.to_lowercase().as_str()))
+            .map(|(name, value, stable)| FingerprintComponent { name: name.to_string(), value, stable })
+            .collect();
+
+        if components.is_empty() {
+            return MachineFingerprint::default();
+        }
+
+        let any_stable = components.iter().any(|component| component.stable);
+        let mut hasher = Sha256::new();
+        for component in components.iter().filter(|component| component.stable || !any_stable) {
+            hasher.update(format!("{}={}\n", component.name, component.value));
+        }
+        let fingerprint = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        MachineFingerprint {
+            fingerprint,
+            components,
+            ..Default::default()
+        }
+    }
+
+    /// 与第一次记录比较，指纹相同而 uuid 不同时认为是克隆
+    pub fn check_uuid(mut self, uuid: &str, previous_fingerprint: &str, previous_uuid: &str) -> Self {
+        self.uuid = uuid.to_string();
+        if !self.fingerprint.is_empty() && self.fingerprint == previous_fingerprint && !previous_uuid.is_empty() {
+            self.previous_uuid = Some(previous_uuid.to_string());
+            self.clone_detected = previous_uuid != uuid;
+        }
+        self
+    }
+}
+
+/// 计算本机指纹，会重新枚举一次显卡
+pub async fn machine_fingerprint() -> MachineFingerprint {
+    let gpu_info = crate::get_gpu_info().await.unwrap_or_default();
+    fingerprint_with_gpus(&gpu_info).await
+}
+
+/// 使用已经获取到的显卡列表计算指纹，并与上次记录的 uuid 比较
+pub async fn fingerprint_with_gpus(gpu_info: &[GpuInfo]) -> MachineFingerprint {
+    let mut gpu_uuids: Vec<&str> = gpu_info
+        .iter()
+        .map(|gpu| gpu.uuid.trim())
+        .filter(|uuid| !uuid.is_empty())
+        .collect();
+    gpu_uuids.sort();
+
+    let fingerprint = MachineFingerprint::from_components(
+        vec![
+            ("machine_id", machine_id()),
+            ("product_uuid", product_uuid()),
+            ("board_serial", board_serial()),
+        ],
+        vec![
+            ("primary_mac", primary_mac()),
+            ("gpu_uuids", gpu_uuids.join(",")),
+        ],
+    );
+
+    let uuid = crate::uuid().await.trim().to_string();
+    let record = crate::read_cache::<FingerprintRecord>("fingerprint.json", u64::MAX);
+    let fingerprint = match &record {
+        Some(record) => fingerprint.check_uuid(&uuid, &record.fingerprint, &record.uuid),
+        None => fingerprint.check_uuid(&uuid, "", ""),
+    };
+    if fingerprint.clone_detected {
+        info!("机器指纹与第一次记录相同但 uuid 已改变: {:?} -> {}", fingerprint.previous_uuid, uuid);
+    }
+
+    if let Some(record) = updated_record(&fingerprint, record) {
+        crate::write_cache("fingerprint.json", &record);
+    }
+
+    fingerprint
+}
+
+/// 确认 uuid 的变化是预期的（比如有意重新注册），之后以当前 uuid 为准，不再报告克隆
+pub async fn acknowledge_uuid_change() {
+    let uuid = crate::uuid().await.trim().to_string();
+    if let Some(mut record) = crate::read_cache::<FingerprintRecord>("fingerprint.json", u64::MAX) {
+        if !uuid.is_empty() && record.uuid != uuid {
+            info!("确认 uuid 变化: {} -> {}", record.uuid, uuid);
+            record.uuid = uuid;
+            crate::write_cache("fingerprint.json", &record);
+        }
+    }
+}
+
+/// 需要写入的记录，没有变化时返回 None；
+/// 指纹不变时保留第一次记录的 uuid，这样克隆状态在确认之前一直存在
+fn updated_record(fingerprint: &MachineFingerprint, record: Option<FingerprintRecord>) -> Option<FingerprintRecord> {
+    if fingerprint.fingerprint.is_empty() || fingerprint.uuid.is_empty() {
+        return None;
+    }
+    match record {
+        Some(record) if record.fingerprint == fingerprint.fingerprint && !record.uuid.is_empty() => None,
+        _ => Some(FingerprintRecord {
+            fingerprint: fingerprint.fingerprint.clone(),
+            uuid: fingerprint.uuid.clone(),
+        }),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn machine_id() -> String {
+    sysfs::read_string("/etc/machine-id")
+        .or_else(|| sysfs::read_string("/var/lib/dbus/machine-id"))
+        .unwrap_or_default()
+}
+
+/// 需要 root 才能读取，读不到时跳过
+#[cfg(not(target_os = "windows"))]
+fn product_uuid() -> String {
+    sysfs::read_string("/sys/class/dmi/id/product_uuid").unwrap_or_default().to_lowercase()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn board_serial() -> String {
+    sysfs::read_string("/sys/class/dmi/id/board_serial").unwrap_or_default()
+}
+
+/// 默认路由所在网卡的 MAC，没有默认路由时取第一张物理网卡
+#[cfg(not(target_os = "windows"))]
+fn primary_mac() -> String {
+    let route = std::fs::read_to_string("/proc/net/route").unwrap_or_default();
+    let default_interface = route
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.len() > 1 && fields[1] == "00000000")
+        .map(|fields| fields[0].to_string());
+
+    if let Some(interface) = default_interface {
+        if let Some(mac) = sysfs::read_string(format!("/sys/class/net/{}/address", interface)) {
+            return mac;
+        }
+    }
+
+    // 只有物理网卡才有 device 链接，跳过 docker0、veth 等虚拟网卡
+    let mut interfaces: Vec<String> = match std::fs::read_dir("/sys/class/net") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join("device").exists())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => vec![],
+    };
+    interfaces.sort();
+    interfaces
+        .iter()
+        .find_map(|interface| sysfs::read_string(format!("/sys/class/net/{}/address", interface)))
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn machine_id() -> String {
+    let output = wmic_value(&["/C", "reg query HKLM\\SOFTWARE\\Microsoft\\Cryptography /v MachineGuid"]);
+    output.split_whitespace().last().unwrap_or("").to_string()
+}
+
+#[cfg(target_os = "windows")]
+fn product_uuid() -> String {
+    wmic_value(&["/C", "wmic csproduct get UUID"]).to_lowercase()
+}
+
+#[cfg(target_os = "windows")]
+fn board_serial() -> String {
+    wmic_value(&["/C", "wmic baseboard get SerialNumber"])
+}
+
+#[cfg(target_os = "windows")]
+fn primary_mac() -> String {
+    match crate::get_net_info() {
+        Ok(net_info) => net_info.into_iter().map(|net| net.mac.to_lowercase().replace('-', ":")).find(|mac| !mac.is_empty()).unwrap_or_default(),
+        Err(_) => "".to_string(),
+    }
+}
+
+/// 命令输出的最后一个非空行，wmic 中即表头下面的值
+#[cfg(target_os = "windows")]
+fn wmic_value(args: &[&str]) -> String {
+    let output = match std::process::Command::new("cmd").args(args).output() {
+        Ok(output) => output,
+        Err(_) => return "".to_string(),
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .last()
+        .unwrap_or("")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(mac: &str, gpus: &str) -> MachineFingerprint {
+        MachineFingerprint::from_components(
+            vec![
+                ("machine_id", "4c4c4544004a3510804bc4c04f4d4a32".to_string()),
+                ("product_uuid", "4C4C4544-004A-3510-804B-C4C04F4D4A32".to_string()),
+                ("board_serial", "To be filled by O.E.M.".to_string()),
+            ],
+            vec![
+                ("primary_mac", mac.to_string()),
+                ("gpu_uuids", gpus.to_string()),
+            ],
+        )
+    }
+
+    #[test]
+    fn volatile_components_do_not_change_fingerprint() {
+        let first = fingerprint("3c:ec:ef:12:34:56", "GPU-5e2a7c1d,GPU-0b7d3f52");
+        let second = fingerprint("3c:ec:ef:65:43:21", "GPU-0b7d3f52");
+        assert_eq!(first.fingerprint.len(), 64);
+        assert_eq!(first.fingerprint, second.fingerprint);
+
+        // 占位值被跳过，易变的标识照样上报
+        let components: Vec<(&str, bool)> = first.components.iter().map(|c| (c.name.as_str(), c.stable)).collect();
+        assert_eq!(components, vec![
+            ("machine_id", true),
+            ("product_uuid", true),
+            ("primary_mac", false),
+            ("gpu_uuids", false),
+        ]);
+        // 原始值不上报
+        let json = serde_json::to_string(&first).unwrap();
+        assert!(!json.contains("3c:ec:ef"));
+    }
+
+    #[test]
+    fn falls_back_to_volatile_components() {
+        let only_mac = |mac: &str| MachineFingerprint::from_components(
+            vec![("machine_id", "".to_string()), ("product_uuid", "00000000-0000-0000-0000-000000000000".to_string())],
+            vec![("primary_mac", mac.to_string())],
+        );
+        assert!(!only_mac("3c:ec:ef:12:34:56").fingerprint.is_empty());
+        assert_ne!(only_mac("3c:ec:ef:12:34:56").fingerprint, only_mac("3c:ec:ef:65:43:21").fingerprint);
+        assert_eq!(only_mac("00:00:00:00:00:00"), MachineFingerprint::default());
+    }
+
+    #[test]
+    fn clone_state_persists_until_acknowledged() {
+        let current = fingerprint("3c:ec:ef:12:34:56", "");
+
+        // 第一次运行写入记录
+        let first = current.clone().check_uuid("uuid-a", "", "");
+        assert!(!first.clone_detected);
+        let record = updated_record(&first, None).unwrap();
+        assert_eq!(record, FingerprintRecord { fingerprint: current.fingerprint.clone(), uuid: "uuid-a".to_string() });
+
+        // uuid 文件被重建，之后每次运行都要报告，记录保持不变
+        for _ in 0..3 {
+            let cloned = current.clone().check_uuid("uuid-b", &record.fingerprint, &record.uuid);
+            assert!(cloned.clone_detected);
+            assert_eq!(cloned.previous_uuid.as_deref(), Some("uuid-a"));
+            let unchanged = FingerprintRecord { fingerprint: record.fingerprint.clone(), uuid: record.uuid.clone() };
+            assert_eq!(updated_record(&cloned, Some(unchanged)), None);
+        }
+
+        // 硬件变化后以新的指纹重新记录
+        let moved = fingerprint("3c:ec:ef:12:34:56", "").check_uuid("uuid-b", "another", "uuid-a");
+        assert!(!moved.clone_detected);
+        let old = FingerprintRecord { fingerprint: "another".to_string(), uuid: "uuid-a".to_string() };
+        assert_eq!(updated_record(&moved, Some(old)).map(|record| record.uuid), Some("uuid-b".to_string()));
+    }
+
+    #[test]
+    fn nothing_recorded_without_uuid() {
+        let current = fingerprint("", "").check_uuid("", "", "");
+        assert_eq!(updated_record(&current, None), None);
+    }
+}
@@ -0,0 +1,193 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::GpuInfo;
+use crate::pci::{self, PciDevice};
+use crate::sysfs;
+use super::{GpuProbe, drm_cards, hwmon_dir, legacy_memory, legacy_power, legacy_temperature};
+
+const AMD_VENDOR_ID: u32 = 0x1002;
+
+/// 通过 amdgpu 驱动的 sysfs 获取 A 卡信息
+pub struct AmdSysfs {
+    root: PathBuf,
+}
+
+impl Default for AmdSysfs {
+    fn default() -> Self {
+        AmdSysfs::with_root("/sys/class/drm")
+    }
+}
+
+impl AmdSysfs {
+    /// 指定 drm 目录，测试时可以指向伪造的目录树
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> Self {
+        AmdSysfs { root: root.into() }
+    }
+
+    fn cards(&self) -> Vec<PathBuf> {
+        drm_cards(&self.root, AMD_VENDOR_ID)
+    }
+}
+
+impl GpuProbe for AmdSysfs {
+    fn name(&self) -> &'static str {
+        "amdgpu-sysfs"
+    }
+
+    fn vendor(&self) -> &'static str {
+        "amd"
+    }
+
+    fn detect(&self, devices: &[PciDevice]) -> bool {
+        devices.iter().any(|device| device.vendor_id == AMD_VENDOR_ID as u16 && device.is_display())
+    }
+
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        Ok(self.cards()
+            .iter()
+            .enumerate()
+            .map(|(index, device)| amd_gpu_info(index, device))
+            .collect())
+    }
+
+    /// 只读动态数据，不查型号
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        Ok(self.cards()
+            .iter()
+            .enumerate()
+            .map(|(index, device)| amd_gpu_sample(index, device))
+            .collect())
+    }
+}
+
+/// device 是 /sys/class/drm/cardN/device
+fn amd_gpu_info(index: usize, device: &Path) -> GpuInfo {
+    let device_id = sysfs::read_hex(device.join("device")).unwrap_or(0);
+    let name = sysfs::read_string(device.join("product_name"))
+        .or_else(|| pci::model_name(AMD_VENDOR_ID as u16, device_id as u16))
+        .unwrap_or(format!("AMD GPU [{:04x}:{:04x}]", AMD_VENDOR_ID, device_id));
+
+    GpuInfo {
+        name,
+        ..amd_gpu_sample(index, device)
+    }
+}
+
+/// 利用率、显存、温度、功耗
+fn amd_gpu_sample(index: usize, device: &Path) -> GpuInfo {
+    let memory_used_bytes = sysfs::read_u64(device.join("mem_info_vram_used"));
+    let memory_total_bytes = sysfs::read_u64(device.join("mem_info_vram_total"));
+
+    let hwmon = hwmon_dir(device);
+    // temp1_input 单位是千分之一摄氏度，power1_average 单位是微瓦
+    let temperature_celsius = hwmon.as_ref()
+        .and_then(|hwmon| sysfs::read_u64(hwmon.join("temp1_input")))
+        .map(|value| value as f32 / 1000.0);
+    let power_draw_watts = hwmon.as_ref()
+        .and_then(|hwmon| {
+            sysfs::read_u64(hwmon.join("power1_average"))
+                .or_else(|| sysfs::read_u64(hwmon.join("power1_input")))
+        })
+        .map(|value| value as f32 / 1_000_000.0);
+
+    GpuInfo {
+        index: index.to_string(),
+        uuid: sysfs::read_string(device.join("unique_id")).unwrap_or_default(),
+        gpu_bus_id: sysfs::link_name(device).unwrap_or_default(),
+        memory_used: legacy_memory(memory_used_bytes),
+        memory_total: legacy_memory(memory_total_bytes),
+        temperature: legacy_temperature(temperature_celsius),
+        power_draw: legacy_power(power_draw_watts),
+        memory_used_bytes,
+        memory_total_bytes,
+        temperature_celsius,
+        power_draw_watts,
+        utilization_percent: sysfs::read_u64(device.join("gpu_busy_percent")).map(|value| value as f32),
+        vendor: "amd".to_string(),
+        ..Default::default()
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(path: &Path, data: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    /// 按 amdgpu 的布局伪造 drm 目录：cardN/device 是指向 PCI 设备目录的链接
+    fn fake_card(root: &Path, card: &str, address: &str, vendor: &str) -> PathBuf {
+        let device = root.join("devices").join(address);
+        write(&device.join("vendor"), vendor);
+        fs::create_dir_all(root.join(card)).unwrap();
+        std::os::unix::fs::symlink(&device, root.join(card).join("device")).unwrap();
+        device
+    }
+
+    #[test]
+    fn reads_amdgpu_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        let device = fake_card(root, "card0", "0000:03:00.0", "0x1002\n");
+        write(&device.join("device"), "0x744c\n");
+        write(&device.join("product_name"), "AMD Radeon RX 7900 XTX\n");
+        write(&device.join("mem_info_vram_total"), "25753026560\n");
+        write(&device.join("mem_info_vram_used"), "1073741824\n");
+        write(&device.join("gpu_busy_percent"), "37\n");
+        write(&device.join("unique_id"), "9b3a1c0e2d4f5a67\n");
+        write(&device.join("hwmon/hwmon4/temp1_input"), "45000\n");
+        write(&device.join("hwmon/hwmon4/power1_average"), "63000000\n");
+
+        // 显示器接口和其他厂商的显卡都要跳过
+        fs::create_dir_all(root.join("card0-DP-1")).unwrap();
+        fake_card(root, "card1", "0000:65:00.0", "0x10de\n");
+
+        let gpus = AmdSysfs::with_root(root).enumerate().unwrap();
+        assert_eq!(gpus.len(), 1);
+
+        let gpu = &gpus[0];
+        assert_eq!(gpu.index, "0");
+        assert_eq!(gpu.name, "AMD Radeon RX 7900 XTX");
+        assert_eq!(gpu.uuid, "9b3a1c0e2d4f5a67");
+        assert_eq!(gpu.gpu_bus_id, "0000:03:00.0");
+        assert_eq!(gpu.memory_total_bytes, Some(25753026560));
+        assert_eq!(gpu.memory_used_bytes, Some(1 << 30));
+        assert_eq!(gpu.memory_used, "1024 MiB");
+        assert_eq!(gpu.temperature_celsius, Some(45.0));
+        assert_eq!(gpu.temperature, "45");
+        assert_eq!(gpu.power_draw_watts, Some(63.0));
+        assert_eq!(gpu.power_draw, "63.00 W");
+        assert_eq!(gpu.utilization_percent, Some(37.0));
+        assert_eq!(gpu.vendor, "amd");
+
+        // 采样时只有动态数据，不查型号
+        let samples = AmdSysfs::with_root(root).sample().unwrap();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].name, "");
+        assert_eq!(samples[0].uuid, "9b3a1c0e2d4f5a67");
+        assert_eq!(samples[0].memory_used_bytes, Some(1 << 30));
+        assert_eq!(samples[0].temperature_celsius, Some(45.0));
+        assert_eq!(samples[0].power_draw_watts, Some(63.0));
+        assert_eq!(samples[0].utilization_percent, Some(37.0));
+    }
+
+    #[test]
+    fn missing_files_are_none() {
+        let dir = tempfile::tempdir().unwrap();
+        fake_card(dir.path(), "card0", "0000:0a:00.0", "0x1002\n");
+
+        let gpus = AmdSysfs::with_root(dir.path()).enumerate().unwrap();
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].name, "AMD GPU [1002:0000]");
+        assert_eq!(gpus[0].memory_total_bytes, None);
+        assert_eq!(gpus[0].memory_total, "[N/A]");
+        assert_eq!(gpus[0].temperature_celsius, None);
+        assert_eq!(gpus[0].power_draw_watts, None);
+        assert_eq!(gpus[0].utilization_percent, None);
+    }
+}
@@ -0,0 +1,537 @@
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+/// 执行外部命令，测试时可以替换成假的实现
+pub trait CommandRunner: Send + Sync {
+    /// 返回标准输出，退出码非 0 时返回错误
+    fn run(&self, program: &str, args: &[String]) -> Result<String, Box<dyn Error>>;
+}
+
+/// 直接调用系统命令
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[String]) -> Result<String, Box<dyn Error>> {
+        let output = std::process::Command::new(program).args(args).output()?;
+        if !output.status.success() {
+            // nvidia-smi 的错误信息有时写在标准输出里
+            let message = match String::from_utf8_lossy(&output.stderr).trim() {
+                "" => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+                stderr => stderr.to_string(),
+            };
+            return Err(format!("{} {} 执行失败: {}", program, args.join(" "), message).into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// 要操作的显卡，"GPU-..." 形式按 uuid，数字按序号
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuTarget {
+    Uuid(String),
+    Index(u32),
+}
+
+impl GpuTarget {
+    pub fn parse(value: &str) -> GpuTarget {
+        match value.trim().parse::<u32>() {
+            Ok(index) => GpuTarget::Index(index),
+            Err(_) => GpuTarget::Uuid(value.trim().to_string()),
+        }
+    }
+}
+
+impl fmt::Display for GpuTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuTarget::Uuid(uuid) => write!(f, "{}", uuid),
+            GpuTarget::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ComputeMode {
+    Default,
+    ExclusiveProcess,
+}
+
+impl ComputeMode {
+    /// nvidia-smi -c 的参数
+    fn arg(&self) -> &'static str {
+        match self {
+            ComputeMode::Default => "DEFAULT",
+            ComputeMode::ExclusiveProcess => "EXCLUSIVE_PROCESS",
+        }
+    }
+
+    /// nvidia-smi --query-gpu=compute_mode 的输出
+    fn parse(value: &str) -> Option<ComputeMode> {
+        match value.trim() {
+            "Default" => Some(ComputeMode::Default),
+            "Exclusive_Process" => Some(ComputeMode::ExclusiveProcess),
+            _ => None,
+        }
+    }
+}
+
+/// 一次控制操作
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "setting", content = "value", rename_all = "snake_case")]
+pub enum GpuSetting {
+    PersistenceMode(bool),
+    /// 功耗上限，单位瓦
+    PowerLimit(f32),
+    /// 应用频率，单位 MHz
+    ApplicationClocks { memory_mhz: u32, graphics_mhz: u32 },
+    ComputeMode(ComputeMode),
+    /// 把应用频率恢复为默认值
+    ResetClocks,
+}
+
+/// 一次操作的结果，previous 是操作前的值，用于回滚
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GpuChange {
+    pub uuid: String,
+    pub setting: GpuSetting,
+    pub previous: Option<GpuSetting>,
+    pub dry_run: bool,
+    /// 执行（或 dry-run 时将要执行）的命令
+    pub command: Vec<String>,
+}
+
+/// 审计日志中的一条记录
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    timestamp: u64,
+    target: String,
+    #[serde(flatten)]
+    change: Option<&'a GpuChange>,
+    error: Option<String>,
+}
+
+/// 操作前读取到的显卡状态
+#[derive(Debug, Default)]
+struct GpuState {
+    uuid: String,
+    persistence_mode: Option<bool>,
+    power_limit: Option<f32>,
+    min_power_limit: Option<f32>,
+    max_power_limit: Option<f32>,
+    graphics_clock: Option<u32>,
+    memory_clock: Option<u32>,
+    max_graphics_clock: Option<u32>,
+    max_memory_clock: Option<u32>,
+    compute_mode: Option<ComputeMode>,
+}
+
+const STATE_QUERY: &str = "--query-gpu=index,uuid,persistence_mode,power.limit,power.min_limit,power.max_limit,\
+clocks.applications.graphics,clocks.applications.memory,clocks.max.graphics,clocks.max.memory,compute_mode";
+
+/// 按 uuid 或序号控制单张 N 卡，所有操作都会写审计日志
+pub struct GpuControl {
+    runner: Box<dyn CommandRunner>,
+    dry_run: bool,
+    audit_log: Option<String>,
+}
+
+impl Default for GpuControl {
+    fn default() -> Self {
+        let audit_log = match wei_env::home_dir() {
+            Ok(home_dir) => format!("{}gpu_control.log", home_dir),
+            Err(_) => "gpu_control.log".to_string(),
+        };
+        GpuControl {
+            runner: Box::new(SystemRunner),
+            dry_run: false,
+            audit_log: Some(audit_log),
+        }
+    }
+}
+
+impl GpuControl {
+    pub fn with_runner<R: CommandRunner + 'static>(mut self, runner: R) -> Self {
+        self.runner = Box::new(runner);
+        self
+    }
+
+    /// dry-run 时只读取状态和校验参数，不修改显卡
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 审计日志路径，None 时只写运行日志
+    pub fn with_audit_log(mut self, path: Option<String>) -> Self {
+        self.audit_log = path;
+        self
+    }
+
+    pub fn set_persistence_mode(&self, target: &GpuTarget, enabled: bool) -> Result<GpuChange, Box<dyn Error>> {
+        self.apply(target, GpuSetting::PersistenceMode(enabled))
+    }
+
+    pub fn set_power_limit(&self, target: &GpuTarget, watts: f32) -> Result<GpuChange, Box<dyn Error>> {
+        self.apply(target, GpuSetting::PowerLimit(watts))
+    }
+
+    pub fn set_application_clocks(&self, target: &GpuTarget, memory_mhz: u32, graphics_mhz: u32) -> Result<GpuChange, Box<dyn Error>> {
+        self.apply(target, GpuSetting::ApplicationClocks { memory_mhz, graphics_mhz })
+    }
+
+    pub fn set_compute_mode(&self, target: &GpuTarget, mode: ComputeMode) -> Result<GpuChange, Box<dyn Error>> {
+        self.apply(target, GpuSetting::ComputeMode(mode))
+    }
+
+    pub fn reset_clocks(&self, target: &GpuTarget) -> Result<GpuChange, Box<dyn Error>> {
+        self.apply(target, GpuSetting::ResetClocks)
+    }
+
+    /// 恢复 change 之前的值，操作前没有读到旧值时返回错误
+    pub fn rollback(&self, change: &GpuChange) -> Result<GpuChange, Box<dyn Error>> {
+        let previous = change.previous.clone().ok_or("没有可以回滚的旧值")?;
+        self.apply(&GpuTarget::Uuid(change.uuid.clone()), previous)
+    }
+
+    /// 按顺序执行多项设置，其中一项失败时按相反顺序回滚已经生效的设置，再返回这一项的错误
+    pub fn apply_all(&self, target: &GpuTarget, settings: Vec<GpuSetting>) -> Result<Vec<GpuChange>, Box<dyn Error>> {
+        let mut changes: Vec<GpuChange> = vec![];
+        for setting in settings {
+            match self.apply(target, setting) {
+                Ok(change) => changes.push(change),
+                Err(err) => {
+                    for change in changes.iter().rev().filter(|change| !change.dry_run) {
+                        if let Err(rollback_err) = self.rollback(change) {
+                            info!("显卡 {} 回滚 {:?} 失败: {}", change.uuid, change.setting, rollback_err);
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// 读取当前状态、校验参数、执行命令并写审计日志
+    pub fn apply(&self, target: &GpuTarget, setting: GpuSetting) -> Result<GpuChange, Box<dyn Error>> {
+        let result = self.change(target, setting);
+        self.audit(target, &result);
+        result
+    }
+
+    fn change(&self, target: &GpuTarget, setting: GpuSetting) -> Result<GpuChange, Box<dyn Error>> {
+        let state = self.state(target)?;
+        validate(&state, &setting)?;
+
+        let mut command = vec!["-i".to_string(), state.uuid.clone()];
+        command.extend(setting_args(&setting));
+
+        let change = GpuChange {
+            uuid: state.uuid.clone(),
+            previous: previous(&state, &setting),
+            setting,
+            dry_run: self.dry_run,
+            command,
+        };
+
+        if !self.dry_run {
+            self.runner.run("nvidia-smi", &change.command)?;
+        }
+        Ok(change)
+    }
+
+    fn state(&self, target: &GpuTarget) -> Result<GpuState, Box<dyn Error>> {
+        let args = vec![STATE_QUERY.to_string(), "--format=csv,noheader,nounits".to_string()];
+        let output = self.runner.run("nvidia-smi", &args)?;
+
+        output
+            .lines()
+            .map(|line| line.split(',').map(|s| s.trim()).collect::<Vec<_>>())
+            .filter(|fields| fields.len() >= 11)
+            .find(|fields| match target {
+                GpuTarget::Uuid(uuid) => fields[1] == uuid,
+                GpuTarget::Index(index) => fields[0] == index.to_string(),
+            })
+            .map(|fields| GpuState {
+                uuid: fields[1].to_string(),
+                persistence_mode: match fields[2] {
+                    "Enabled" => Some(true),
+                    "Disabled" => Some(false),
+                    _ => None,
+                },
+                power_limit: fields[3].parse().ok(),
+                min_power_limit: fields[4].parse().ok(),
+                max_power_limit: fields[5].parse().ok(),
+                graphics_clock: fields[6].parse().ok(),
+                memory_clock: fields[7].parse().ok(),
+                max_graphics_clock: fields[8].parse().ok(),
+                max_memory_clock: fields[9].parse().ok(),
+                compute_mode: ComputeMode::parse(fields[10]),
+            })
+            .ok_or_else(|| format!("找不到显卡 {}", target).into())
+    }
+
+    fn audit(&self, target: &GpuTarget, result: &Result<GpuChange, Box<dyn Error>>) {
+        match result {
+            Ok(change) => info!("显卡 {} 设置 {:?}，旧值 {:?}，dry-run: {}", change.uuid, change.setting, change.previous, change.dry_run),
+            Err(err) => info!("显卡 {} 设置失败: {}", target, err),
+        }
+
+        let path = match &self.audit_log {
+            Some(path) => path,
+            None => return,
+        };
+        let entry = AuditEntry {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            target: target.to_string(),
+            change: result.as_ref().ok(),
+            error: result.as_ref().err().map(|err| err.to_string()),
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(err) = written {
+            info!("写入审计日志 {} 失败: {}", path, err);
+        }
+    }
+}
+
+fn validate(state: &GpuState, setting: &GpuSetting) -> Result<(), Box<dyn Error>> {
+    match setting {
+        GpuSetting::PowerLimit(watts) => {
+            let (min, max) = match (state.min_power_limit, state.max_power_limit) {
+                (Some(min), Some(max)) => (min, max),
+                _ => return Err(format!("显卡 {} 不支持设置功耗上限", state.uuid).into()),
+            };
+            if *watts < min || *watts > max {
+                return Err(format!("功耗上限 {} W 超出范围 {} W - {} W", watts, min, max).into());
+            }
+        }
+        GpuSetting::ApplicationClocks { memory_mhz, graphics_mhz } => {
+            if *memory_mhz == 0 || *graphics_mhz == 0 {
+                return Err("应用频率不能为 0".into());
+            }
+            if state.max_memory_clock.is_some_and(|max| *memory_mhz > max) {
+                return Err(format!("显存频率 {} MHz 超过最大值 {:?} MHz", memory_mhz, state.max_memory_clock).into());
+            }
+            if state.max_graphics_clock.is_some_and(|max| *graphics_mhz > max) {
+                return Err(format!("核心频率 {} MHz 超过最大值 {:?} MHz", graphics_mhz, state.max_graphics_clock).into());
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 能够恢复到当前状态的操作，读不到当前值时为 None
+fn previous(state: &GpuState, setting: &GpuSetting) -> Option<GpuSetting> {
+    match setting {
+        GpuSetting::PersistenceMode(_) => state.persistence_mode.map(GpuSetting::PersistenceMode),
+        GpuSetting::PowerLimit(_) => state.power_limit.map(GpuSetting::PowerLimit),
+        GpuSetting::ComputeMode(_) => state.compute_mode.map(GpuSetting::ComputeMode),
+        GpuSetting::ApplicationClocks { .. } | GpuSetting::ResetClocks => match (state.memory_clock, state.graphics_clock) {
+            (Some(memory_mhz), Some(graphics_mhz)) => Some(GpuSetting::ApplicationClocks { memory_mhz, graphics_mhz }),
+            _ => None,
+        },
+    }
+}
+
+fn setting_args(setting: &GpuSetting) -> Vec<String> {
+    match setting {
+        GpuSetting::PersistenceMode(enabled) => vec!["-pm".to_string(), if *enabled { "1" } else { "0" }.to_string()],
+        GpuSetting::PowerLimit(watts) => vec!["-pl".to_string(), watts.to_string()],
+        GpuSetting::ApplicationClocks { memory_mhz, graphics_mhz } => {
+            vec!["-ac".to_string(), format!("{},{}", memory_mhz, graphics_mhz)]
+        }
+        GpuSetting::ComputeMode(mode) => vec!["-c".to_string(), mode.arg().to_string()],
+        GpuSetting::ResetClocks => vec!["-rac".to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // nvidia-smi --query-gpu=... --format=csv,noheader,nounits
+    const STATE: &str = "\
+0, GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11, Disabled, 300.00, 100.00, 400.00, 1215, 1593, 1410, 1593, Default
+1, GPU-0b7d3f52-1c8e-4a67-9d21-7e5c3a8b6f90, Enabled, 250.00, 100.00, 250.00, [N/A], [N/A], 1410, 1593, Exclusive_Process
+";
+    const UUID: &str = "GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11";
+
+    /// 记录所有命令，查询状态时返回 STATE，参数中含有 fail_on 时返回错误
+    #[derive(Clone, Default)]
+    struct MockRunner {
+        commands: Arc<Mutex<Vec<Vec<String>>>>,
+        fail_on: Option<&'static str>,
+    }
+
+    impl MockRunner {
+        /// 修改显卡的命令，不含查询状态
+        fn changes(&self) -> Vec<Vec<String>> {
+            self.commands.lock().unwrap()
+                .iter()
+                .filter(|args| !args[0].starts_with("--query-gpu"))
+                .cloned()
+                .collect()
+        }
+    }
+
+    impl CommandRunner for MockRunner {
+        fn run(&self, program: &str, args: &[String]) -> Result<String, Box<dyn Error>> {
+            assert_eq!(program, "nvidia-smi");
+            self.commands.lock().unwrap().push(args.to_vec());
+            if args[0].starts_with("--query-gpu") {
+                return Ok(STATE.to_string());
+            }
+            if self.fail_on.is_some_and(|arg| args.iter().any(|a| a == arg)) {
+                return Err(format!("nvidia-smi {} 执行失败: Insufficient Permissions", args.join(" ")).into());
+            }
+            Ok(String::new())
+        }
+    }
+
+    fn control(runner: &MockRunner) -> GpuControl {
+        GpuControl::default().with_runner(runner.clone()).with_audit_log(None)
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn validates_power_limit_range() {
+        let runner = MockRunner::default();
+        let control = control(&runner);
+        let target = GpuTarget::Index(0);
+
+        assert!(control.set_power_limit(&target, 99.0).is_err());
+        assert!(control.set_power_limit(&target, 400.5).is_err());
+        assert!(runner.changes().is_empty());
+
+        let change = control.set_power_limit(&target, 400.0).unwrap();
+        assert_eq!(change.uuid, UUID);
+        assert_eq!(change.previous, Some(GpuSetting::PowerLimit(300.0)));
+        assert_eq!(runner.changes(), vec![args(&["-i", UUID, "-pl", "400"])]);
+    }
+
+    #[test]
+    fn validates_application_clocks() {
+        let runner = MockRunner::default();
+        let control = control(&runner);
+        let target = GpuTarget::Uuid(UUID.to_string());
+
+        assert!(control.set_application_clocks(&target, 1594, 1410).is_err());
+        assert!(control.set_application_clocks(&target, 1593, 1411).is_err());
+        assert!(control.set_application_clocks(&target, 0, 1410).is_err());
+        assert!(runner.changes().is_empty());
+
+        let change = control.set_application_clocks(&target, 1593, 1410).unwrap();
+        assert_eq!(change.previous, Some(GpuSetting::ApplicationClocks { memory_mhz: 1593, graphics_mhz: 1215 }));
+        assert_eq!(runner.changes(), vec![args(&["-i", UUID, "-ac", "1593,1410"])]);
+    }
+
+    #[test]
+    fn unknown_target_is_an_error() {
+        let runner = MockRunner::default();
+        let err = control(&runner).set_persistence_mode(&GpuTarget::Index(7), true).unwrap_err();
+        assert_eq!(err.to_string(), "找不到显卡 7");
+        assert!(runner.changes().is_empty());
+    }
+
+    #[test]
+    fn dry_run_issues_no_commands() {
+        let runner = MockRunner::default();
+        let control = control(&runner).dry_run(true);
+        let target = GpuTarget::Index(1);
+
+        let change = control.set_compute_mode(&target, ComputeMode::Default).unwrap();
+        assert!(change.dry_run);
+        assert_eq!(change.command, args(&["-i", "GPU-0b7d3f52-1c8e-4a67-9d21-7e5c3a8b6f90", "-c", "DEFAULT"]));
+        assert_eq!(change.previous, Some(GpuSetting::ComputeMode(ComputeMode::ExclusiveProcess)));
+        // 读不到当前的应用频率时没有可回滚的旧值
+        assert_eq!(control.reset_clocks(&target).unwrap().previous, None);
+        control.apply_all(&target, vec![GpuSetting::PersistenceMode(false), GpuSetting::PowerLimit(200.0)]).unwrap();
+
+        // 参数校验照常进行
+        assert!(control.set_power_limit(&target, 300.0).is_err());
+        assert!(runner.changes().is_empty());
+    }
+
+    #[test]
+    fn rolls_back_when_second_setting_fails() {
+        let runner = MockRunner { fail_on: Some("-ac"), ..Default::default() };
+        let control = control(&runner);
+
+        let err = control.apply_all(&GpuTarget::Index(0), vec![
+            GpuSetting::PersistenceMode(true),
+            GpuSetting::PowerLimit(350.0),
+            GpuSetting::ApplicationClocks { memory_mhz: 1593, graphics_mhz: 1410 },
+        ]).unwrap_err();
+        assert!(err.to_string().contains("Insufficient Permissions"));
+
+        assert_eq!(runner.changes(), vec![
+            args(&["-i", UUID, "-pm", "1"]),
+            args(&["-i", UUID, "-pl", "350"]),
+            args(&["-i", UUID, "-ac", "1593,1410"]),
+            // 按相反顺序恢复旧值
+            args(&["-i", UUID, "-pl", "300"]),
+            args(&["-i", UUID, "-pm", "0"]),
+        ]);
+    }
+
+    #[test]
+    fn rollback_restores_previous_value() {
+        let runner = MockRunner::default();
+        let control = control(&runner);
+
+        let change = control.set_persistence_mode(&GpuTarget::Index(0), true).unwrap();
+        let rollback = control.rollback(&change).unwrap();
+        assert_eq!(rollback.setting, GpuSetting::PersistenceMode(false));
+        assert_eq!(runner.changes().last(), Some(&args(&["-i", UUID, "-pm", "0"])));
+
+        let no_previous = GpuChange { previous: None, ..change };
+        assert!(control.rollback(&no_previous).is_err());
+    }
+
+    #[test]
+    fn writes_audit_log_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpu_control.log");
+        let runner = MockRunner::default();
+        let control = control(&runner).with_audit_log(Some(path.to_string_lossy().to_string()));
+
+        control.set_power_limit(&GpuTarget::Index(0), 250.0).unwrap();
+        control.set_power_limit(&GpuTarget::Index(0), 500.0).unwrap_err();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0]["target"], "0");
+        assert_eq!(lines[0]["uuid"], UUID);
+        assert_eq!(lines[0]["setting"], serde_json::json!({"setting": "power_limit", "value": 250.0}));
+        assert_eq!(lines[0]["previous"], serde_json::json!({"setting": "power_limit", "value": 300.0}));
+        assert_eq!(lines[0]["dry_run"], false);
+        assert_eq!(lines[0]["command"], serde_json::json!(["-i", UUID, "-pl", "250"]));
+        assert!(lines[0]["error"].is_null());
+        assert!(lines[0]["timestamp"].as_u64().is_some());
+
+        assert_eq!(lines[1]["target"], "0");
+        assert!(lines[1]["error"].as_str().unwrap().contains("超出范围"));
+        assert!(lines[1].get("uuid").is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::GpuInfo;
+use crate::pci::PciDevice;
+use super::{GpuProbe, legacy_memory, legacy_power, legacy_temperature, parse_quantity};
+
+/// 通过 npu-smi 获取华为昇腾 NPU 信息
+pub struct AscendNpuSmi;
+
+impl GpuProbe for AscendNpuSmi {
+    fn name(&self) -> &'static str {
+        "npu-smi"
+    }
+
+    fn vendor(&self) -> &'static str {
+        "huawei"
+    }
+
+    fn detect(&self, devices: &[PciDevice]) -> bool {
+        devices.iter().any(|device| device.vendor_id == 0x19e5 && device.is_accelerator())
+    }
+
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        let output = npu_smi(&["info"])?;
+        let mut gpu_info = parse_npu_smi_info(&output);
+
+        // 序列号只能逐个设备查询，一卡多芯片时同一张卡只查一次
+        let mut serials: HashMap<String, Option<String>> = HashMap::new();
+        assign_uuids(&mut gpu_info, |npu_id| {
+            serials
+                .entry(npu_id.to_string())
+                .or_insert_with(|| match npu_smi(&["info", "-t", "board", "-i", npu_id]) {
+                    Ok(board) => npu_smi_value(&board, "Serial Number"),
+                    Err(err) => {
+                        info!("npu-smi info -t board -i {} 执行失败: {}", npu_id, err);
+                        None
+                    }
+                })
+                .clone()
+        });
+
+        Ok(gpu_info)
+    }
+
+    /// 只跑一次 npu-smi info，不逐个查询序列号，采样时按序号区分设备
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        Ok(parse_npu_smi_info(&npu_smi(&["info"])?))
+    }
+}
+
+fn npu_smi(args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = match std::process::Command::new("npu-smi").args(args).output() {
+        Ok(output) => output,
+        Err(_) => return Err("npu-smi 执行失败".into()),
+    };
+
+    if !output.status.success() {
+        return Err(format!("npu-smi 执行失败: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 解析 `npu-smi info` 的表格，每个 NPU 占两行：
+///
+/// ```text
+/// | NPU   Name                | Health        | Power(W)    Temp(C)           Hugepages-Usage(page)|
+/// | Chip                      | Bus-Id        | AICore(%)   Memory-Usage(MB)  HBM-Usage(MB)        |
+/// +===========================+===============+====================================================+
+/// | 0     910B                | OK            | 93.5        40                0    / 0             |
+/// | 0                         | 0000:C1:00.0  | 0           0    / 0          3161 / 65536         |
+/// ```
+///
+/// 310P 等一卡多芯片的型号，一个 NPU 行后面跟多个 Chip 行，每个芯片输出一条
+pub fn parse_npu_smi_info(output: &str) -> Vec<GpuInfo> {
+    let pair = regex::Regex::new(r"(\d+)\s*/\s*(\d+)").unwrap();
+
+    let mut gpu_info: Vec<GpuInfo> = vec![];
+    let mut npu: Option<NpuRow> = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        // 进程表格在设备表格之后，到这里就结束了
+        if line.contains("Process id") || line.contains("Process name") {
+            break;
+        }
+        if !line.starts_with('|') {
+            continue;
+        }
+
+        let cells: Vec<&str> = line.trim_matches('|').split('|').map(|cell| cell.trim()).collect();
+        if cells.len() < 3 || cells[1].starts_with("Health") || cells[1].starts_with("Bus-Id") {
+            continue;
+        }
+
+        if is_bus_id(cells[1]) {
+            let npu = match &npu {
+                Some(npu) => npu,
+                None => continue,
+            };
+
+            let chip = cells[0].split_whitespace().next().unwrap_or("0");
+            let usages: Vec<(u64, u64)> = pair
+                .captures_iter(cells[2])
+                .filter_map(|cap| Some((cap[1].parse().ok()?, cap[2].parse().ok()?)))
+                .collect();
+            // 910 系列有 Memory-Usage 和 HBM-Usage 两组，HBM 在后面；310P 只有 Memory-Usage
+            let (memory_used, memory_total) = match usages.last() {
+                Some(&(used, total)) if total > 0 => (Some(used << 20), Some(total << 20)),
+                _ => match usages.first() {
+                    Some(&(used, total)) => (Some(used << 20), Some(total << 20)),
+                    None => (None, None),
+                },
+            };
+            let ai_core = cells[2]
+                .split_whitespace()
+                .next()
+                .and_then(|value| value.parse::<f32>().ok());
+
+            gpu_info.push(GpuInfo {
+                index: if chip == "0" { npu.id.clone() } else { format!("{}-{}", npu.id, chip) },
+                name: npu.name.clone(),
+                uuid: "".to_string(),
+                gpu_bus_id: cells[1].to_string(),
+                memory_used: legacy_memory(memory_used),
+                memory_total: legacy_memory(memory_total),
+                temperature: legacy_temperature(npu.temperature),
+                power_draw: legacy_power(npu.power),
+                memory_used_bytes: memory_used,
+                memory_total_bytes: memory_total,
+                temperature_celsius: npu.temperature,
+                power_draw_watts: npu.power,
+                utilization_percent: ai_core,
+                health: Some(npu.health.clone()),
+                vendor: "huawei".to_string(),
+                ..Default::default()
+            });
+        } else {
+            let mut id_name = cells[0].split_whitespace();
+            let npu_id = match id_name.next() {
+                Some(id) if id.parse::<u32>().is_ok() => id.to_string(),
+                _ => continue,
+            };
+            let name = id_name.collect::<Vec<&str>>().join(" ");
+            let mut values = cells[2].split_whitespace();
+            let power = values.next().and_then(parse_quantity).map(|(value, _)| value as f32);
+            let temperature = values.next().and_then(parse_quantity).map(|(value, _)| value as f32);
+
+            npu = Some(NpuRow {
+                id: npu_id,
+                name,
+                health: cells[1].to_string(),
+                power,
+                temperature,
+            });
+        }
+    }
+
+    gpu_info
+}
+
+/// 用板卡序列号生成 uuid，310P 等一卡多芯片时同卡芯片共用序列号，
+/// 除 0 号芯片外在序列号后面加上芯片号，与 index 的 `1`、`1-1` 对应
+pub fn assign_uuids<F>(gpu_info: &mut [GpuInfo], mut serial_of: F)
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    for gpu in gpu_info.iter_mut() {
+        let (npu_id, chip) = match gpu.index.split_once('-') {
+            Some((npu_id, chip)) => (npu_id, Some(chip)),
+            None => (gpu.index.as_str(), None),
+        };
+        let serial = match serial_of(npu_id) {
+            Some(serial) if !serial.is_empty() => serial,
+            _ => continue,
+        };
+        gpu.uuid = match chip {
+            Some(chip) => format!("{}-{}", serial, chip),
+            None => serial,
+        };
+    }
+}
+
+/// 表格中 NPU 行的数据，后面的 Chip 行共用
+struct NpuRow {
+    id: String,
+    name: String,
+    health: String,
+    power: Option<f32>,
+    temperature: Option<f32>,
+}
+
+/// 取 `npu-smi info -t <type> -i <id>` 输出中 `Key : Value` 形式的值
+pub fn npu_smi_value(output: &str, key: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim() == key {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn is_bus_id(cell: &str) -> bool {
+    let mut parts = cell.split(':');
+    parts.clone().count() == 3 && parts.all(|part| !part.is_empty()) && cell.contains('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NPU_SMI_910B: &str = "\
++------------------------------------------------------------------------------------------------+
+| npu-smi 23.0.0                   Version: 23.0.0                                               |
++---------------------------+---------------+----------------------------------------------------+
+| NPU   Name                | Health        | Power(W)    Temp(C)           Hugepages-Usage(page)|
+| Chip                      | Bus-Id        | AICore(%)   Memory-Usage(MB)  HBM-Usage(MB)        |
++===========================+===============+====================================================+
+| 0     910B3               | OK            | 93.5        40                0    / 0             |
+| 0                         | 0000:C1:00.0  | 0           0    / 0          3161 / 65536         |
++===========================+===============+====================================================+
+| 1     910B3               | Warning       | 101.2       52                0    / 0             |
+| 0                         | 0000:C2:00.0  | 87          0    / 0          61020/ 65536         |
++===========================+===============+====================================================+
++---------------------------+---------------+----------------------------------------------------+
+| NPU     Chip              | Process id    | Process name             | Process memory(MB)      |
++===========================+===============+====================================================+
+| 1       0                 | 183562        | python3.9                | 57859                   |
++===========================+===============+====================================================+
+";
+
+    // 310P 一张卡两个芯片，共用一个 Bus-Id，功耗读不到时为 NA
+    const NPU_SMI_310P: &str = "\
++--------------------------------------------------------------------------------------------------------+
+| npu-smi 23.0.rc3                                 Version: 23.0.rc3                                     |
++-------------------------------+-----------------+------------------------------------------------------+
+| NPU     Name                  | Health          | Power(W)     Temp(C)           Hugepages-Usage(page) |
+| Chip    Device                | Bus-Id          | AICore(%)    Memory-Usage(MB)                        |
++===============================+=================+======================================================+
+| 1       310P3                 | OK              | NA           42                0     / 0             |
+| 0       0                     | 0000:01:00.0    | 0            1782 / 21527                            |
+| 1       1                     | 0000:01:00.0    | 12           1525 / 21527                            |
++===============================+=================+======================================================+
+| 2       310P3                 | OK              | NA           44                0     / 0             |
+| 0       2                     | 0000:02:00.0    | 0            1780 / 21527                            |
+| 1       3                     | 0000:02:00.0    | 0            1524 / 21527                            |
++===============================+=================+======================================================+
+";
+
+    #[test]
+    fn parses_910b() {
+        let npus = parse_npu_smi_info(NPU_SMI_910B);
+        assert_eq!(npus.len(), 2);
+
+        let npu = &npus[0];
+        assert_eq!(npu.index, "0");
+        assert_eq!(npu.name, "910B3");
+        assert_eq!(npu.gpu_bus_id, "0000:C1:00.0");
+        assert_eq!(npu.health.as_deref(), Some("OK"));
+        assert_eq!(npu.power_draw_watts, Some(93.5));
+        assert_eq!(npu.power_draw, "93.50 W");
+        assert_eq!(npu.temperature_celsius, Some(40.0));
+        // 取 HBM-Usage 而不是 Memory-Usage
+        assert_eq!(npu.memory_used_bytes, Some(3161 << 20));
+        assert_eq!(npu.memory_total_bytes, Some(65536 << 20));
+        assert_eq!(npu.memory_total, "65536 MiB");
+        assert_eq!(npu.utilization_percent, Some(0.0));
+        assert_eq!(npu.vendor, "huawei");
+
+        let npu = &npus[1];
+        assert_eq!(npu.index, "1");
+        assert_eq!(npu.health.as_deref(), Some("Warning"));
+        assert_eq!(npu.memory_used_bytes, Some(61020 << 20));
+        assert_eq!(npu.utilization_percent, Some(87.0));
+    }
+
+    #[test]
+    fn parses_310p_chips() {
+        let chips = parse_npu_smi_info(NPU_SMI_310P);
+        let indexes: Vec<&str> = chips.iter().map(|chip| chip.index.as_str()).collect();
+        assert_eq!(indexes, vec!["1", "1-1", "2", "2-1"]);
+
+        for chip in &chips {
+            assert_eq!(chip.name, "310P3");
+            assert_eq!(chip.power_draw_watts, None);
+            assert_eq!(chip.power_draw, "[N/A]");
+            assert_eq!(chip.memory_total_bytes, Some(21527 << 20));
+        }
+
+        assert_eq!(chips[0].gpu_bus_id, "0000:01:00.0");
+        assert_eq!(chips[1].gpu_bus_id, "0000:01:00.0");
+        assert_eq!(chips[0].memory_used_bytes, Some(1782 << 20));
+        assert_eq!(chips[1].memory_used_bytes, Some(1525 << 20));
+        assert_eq!(chips[1].utilization_percent, Some(12.0));
+        assert_eq!(chips[2].temperature_celsius, Some(44.0));
+        assert_eq!(chips[3].gpu_bus_id, "0000:02:00.0");
+    }
+
+    #[test]
+    fn assigns_distinct_uuids_to_310p_chips() {
+        let mut chips = parse_npu_smi_info(NPU_SMI_310P);
+        let mut queried = vec![];
+        assign_uuids(&mut chips, |npu_id| {
+            queried.push(npu_id.to_string());
+            Some(format!("SN{}", npu_id))
+        });
+
+        let uuids: Vec<&str> = chips.iter().map(|chip| chip.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["SN1", "SN1-1", "SN2", "SN2-1"]);
+        assert_eq!(queried, vec!["1", "1", "2", "2"]);
+
+        // 查不到序列号时保持为空，不拿别的卡凑
+        let mut chips = parse_npu_smi_info(NPU_SMI_310P);
+        assign_uuids(&mut chips, |npu_id| if npu_id == "1" { Some("SN1".to_string()) } else { None });
+        let uuids: Vec<&str> = chips.iter().map(|chip| chip.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["SN1", "SN1-1", "", ""]);
+    }
+
+    #[test]
+    fn detects_accelerator_but_not_bmc_vga() {
+        let npu = PciDevice { vendor_id: 0x19e5, device_id: 0xd500, class: 0x120000, ..Default::default() };
+        // iBMC 的 Hi1710 VGA 同样是华为厂商号
+        let vga = PciDevice { vendor_id: 0x19e5, device_id: 0x1711, class: 0x030000, ..Default::default() };
+
+        assert!(AscendNpuSmi.detect(&[npu, vga.clone()]));
+        assert!(!AscendNpuSmi.detect(&[vga]));
+    }
+
+    #[test]
+    fn reads_board_values() {
+        let board = "\
+        NPU ID                         : 0
+        Product Name                   : Atlas 800T A2
+        Serial Number                  : 102312345678
+        Chip Count                     : 1
+";
+        assert_eq!(npu_smi_value(board, "Serial Number").as_deref(), Some("102312345678"));
+        assert_eq!(npu_smi_value(board, "Firmware Version"), None);
+    }
+}
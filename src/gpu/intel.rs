@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::GpuInfo;
+use crate::pci::{self, PciDevice};
+use crate::sysfs;
+use super::{GpuProbe, drm_cards, hwmon_dir, legacy_memory, legacy_power, legacy_temperature};
+
+const INTEL_VENDOR_ID: u32 = 0x8086;
+
+/// 通过 i915 / xe 驱动的 sysfs 获取 Intel 独显和核显信息
+pub struct IntelSysfs {
+    root: PathBuf,
+    /// 上一次读到的累计能耗（微焦）和读取时间，按 hwmon 目录区分
+    energy: Mutex<HashMap<PathBuf, (u64, Instant)>>,
+}
+
+impl Default for IntelSysfs {
+    fn default() -> Self {
+        IntelSysfs::with_root("/sys/class/drm")
+    }
+}
+
+impl IntelSysfs {
+    /// 指定 drm 目录，测试时可以指向伪造的目录树
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> Self {
+        IntelSysfs {
+            root: root.into(),
+            energy: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 绑定了 i915 或 xe 驱动的 Intel 显卡，返回 cardN 目录
+    fn cards(&self) -> Vec<PathBuf> {
+        drm_cards(&self.root, INTEL_VENDOR_ID)
+            .into_iter()
+            .filter(|device| {
+                matches!(sysfs::link_name(device.join("driver")).as_deref(), Some("i915") | Some("xe"))
+            })
+            .filter_map(|device| device.parent().map(|card| card.to_path_buf()))
+            .collect()
+    }
+
+    /// card 是 /sys/class/drm/cardN
+    fn gpu_info(&self, index: usize, card: &Path) -> GpuInfo {
+        let device = card.join("device");
+        let device_id = sysfs::read_hex(device.join("device")).unwrap_or(0);
+        let xe = sysfs::link_name(device.join("driver")).as_deref() == Some("xe");
+
+        let (clock_mhz, max_clock_mhz) = if xe {
+            let freq = device.join("tile0/gt0/freq0");
+            (sysfs::read_u64(freq.join("cur_freq")), sysfs::read_u64(freq.join("max_freq")))
+        } else {
+            (sysfs::read_u64(card.join("gt_cur_freq_mhz")), sysfs::read_u64(card.join("gt_max_freq_mhz")))
+        };
+
+        GpuInfo {
+            name: intel_gpu_name(device_id),
+            clock_mhz: clock_mhz.map(|value| value as u32),
+            max_clock_mhz: max_clock_mhz.map(|value| value as u32),
+            ..self.gpu_sample(index, card)
+        }
+    }
+
+    /// 显存、温度、功耗，不查型号和频率
+    fn gpu_sample(&self, index: usize, card: &Path) -> GpuInfo {
+        let device = card.join("device");
+        let xe = sysfs::link_name(device.join("driver")).as_deref() == Some("xe");
+
+        // 核显没有独立显存，这些文件不存在
+        let (memory_total_bytes, memory_used_bytes) = if xe {
+            (sysfs::read_u64(device.join("tile0/physical_vram_size_bytes")), None)
+        } else {
+            let total = sysfs::read_u64(card.join("lmem_total_bytes"));
+            let used = total.zip(sysfs::read_u64(card.join("lmem_avail_bytes")))
+                .map(|(total, avail)| total.saturating_sub(avail));
+            (total, used)
+        };
+
+        let hwmon = hwmon_dir(&device);
+        let temperature_celsius = hwmon.as_ref()
+            .and_then(|hwmon| sysfs::read_u64(hwmon.join("temp1_input")))
+            .map(|value| value as f32 / 1000.0);
+        let power_draw_watts = hwmon.as_ref().and_then(|hwmon| self.hwmon_power(hwmon));
+
+        GpuInfo {
+            index: index.to_string(),
+            uuid: "".to_string(),
+            gpu_bus_id: sysfs::link_name(&device).unwrap_or_default(),
+            memory_used: legacy_memory(memory_used_bytes),
+            memory_total: legacy_memory(memory_total_bytes),
+            temperature: legacy_temperature(temperature_celsius),
+            power_draw: legacy_power(power_draw_watts),
+            memory_used_bytes,
+            memory_total_bytes,
+            temperature_celsius,
+            power_draw_watts,
+            vendor: "intel".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Intel 的 hwmon 一般只有累计能耗 energy1_input（微焦），用相邻两次采样的差值换算成功率，
+    /// 第一次采样没有上一次的读数，返回 None
+    fn hwmon_power(&self, hwmon: &Path) -> Option<f32> {
+        if let Some(power) = sysfs::read_u64(hwmon.join("power1_average"))
+            .or_else(|| sysfs::read_u64(hwmon.join("power1_input"))) {
+            return Some(power as f32 / 1_000_000.0);
+        }
+
+        let energy = sysfs::read_u64(hwmon.join("energy1_input"))?;
+        let now = Instant::now();
+        let mut readings = self.energy.lock().ok()?;
+        let (previous, at) = readings.insert(hwmon.to_path_buf(), (energy, now))?;
+        let seconds = now.duration_since(at).as_secs_f32();
+        if seconds <= 0.0 || energy < previous {
+            return None;
+        }
+
+        Some((energy - previous) as f32 / 1_000_000.0 / seconds)
+    }
+}
+
+impl GpuProbe for IntelSysfs {
+    fn name(&self) -> &'static str {
+        "intel-sysfs"
+    }
+
+    fn vendor(&self) -> &'static str {
+        "intel"
+    }
+
+    fn detect(&self, devices: &[PciDevice]) -> bool {
+        devices.iter().any(|device| {
+            device.vendor_id == INTEL_VENDOR_ID as u16
+                && device.is_display()
+                && matches!(device.driver.as_deref(), Some("i915") | Some("xe"))
+        })
+    }
+
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        Ok(self.cards()
+            .iter()
+            .enumerate()
+            .map(|(index, card)| self.gpu_info(index, card))
+            .collect())
+    }
+
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        Ok(self.cards()
+            .iter()
+            .enumerate()
+            .map(|(index, card)| self.gpu_sample(index, card))
+            .collect())
+    }
+}
+
+/// 从 pci.ids 查询型号，如 "DG2 [Arc A770]" 返回 "Intel Arc A770"
+fn intel_gpu_name(device_id: u32) -> String {
+    match pci::model_name(INTEL_VENDOR_ID as u16, device_id as u16) {
+        Some(name) if name.starts_with("Intel") => name,
+        Some(name) => format!("Intel {}", name),
+        None => format!("Intel GPU [{:04x}:{:04x}]", INTEL_VENDOR_ID, device_id),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn energy_counter_needs_two_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let device = root.join("devices/0000:03:00.0");
+        let hwmon = device.join("hwmon/hwmon2");
+        fs::create_dir_all(&hwmon).unwrap();
+        fs::create_dir_all(root.join("drivers/i915")).unwrap();
+        fs::create_dir_all(root.join("card0")).unwrap();
+        fs::write(device.join("vendor"), "0x8086\n").unwrap();
+        fs::write(device.join("device"), "0x56a0\n").unwrap();
+        std::os::unix::fs::symlink(root.join("drivers/i915"), device.join("driver")).unwrap();
+        std::os::unix::fs::symlink(&device, root.join("card0/device")).unwrap();
+        fs::write(hwmon.join("energy1_input"), "1000000000\n").unwrap();
+
+        let probe = IntelSysfs::with_root(root);
+        let first = probe.enumerate().unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].gpu_bus_id, "0000:03:00.0");
+        assert_eq!(first[0].power_draw_watts, None);
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(hwmon.join("energy1_input"), "1001000000\n").unwrap();
+        let second = probe.enumerate().unwrap();
+        assert!(second[0].power_draw_watts.is_some_and(|watts| watts > 0.0));
+
+        // 有 power1_average 时直接使用
+        fs::write(hwmon.join("power1_average"), "35000000\n").unwrap();
+        assert_eq!(probe.enumerate().unwrap()[0].power_draw_watts, Some(35.0));
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use serde::{Serialize, Deserialize};
+
+use crate::GpuInfo;
+
+/// MIG 的 GPU 实例（GI），对应 nvidia-smi mig -lgi 中的一行
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MigInstance {
+    pub gpu_instance_id: u32,
+    pub profile_id: u32,
+    /// 如 "MIG 3g.20gb"
+    pub profile: String,
+    /// 显存切片，如 "20gb"
+    pub memory_slice: String,
+    /// 显存切片的标称大小，单位字节
+    pub memory_bytes: Option<u64>,
+    /// 在 8 个显存切片中的起始位置和数量
+    pub placement_start: u32,
+    pub placement_size: u32,
+    pub compute_instances: Vec<MigComputeInstance>,
+}
+
+/// MIG 的计算实例（CI），对应 nvidia-smi mig -lci 中的一行，容器里看到的就是它
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MigComputeInstance {
+    pub compute_instance_id: u32,
+    pub profile_id: u32,
+    pub profile: String,
+    pub placement_start: u32,
+    pub placement_size: u32,
+    /// MIG 设备 UUID，对应不上 nvidia-smi -L 时为空
+    pub uuid: String,
+}
+
+/// nvidia-smi -L 中的一个 MIG 设备
+struct MigDevice {
+    gpu: u32,
+    profile: String,
+    uuid: String,
+}
+
+/// 读取 MIG 实例并挂到对应的显卡上，没有开启 MIG 时什么都不做
+pub(crate) fn attach_mig_instances(gpu_info: &mut [GpuInfo]) {
+    let lgi = match nvidia_smi(&["mig", "-lgi"]) {
+        Some(output) => output,
+        None => return,
+    };
+    let lci = nvidia_smi(&["mig", "-lci"]).unwrap_or_default();
+    let devices = nvidia_smi(&["-L"]).unwrap_or_default();
+
+    let mut instances = parse_mig(&lgi, &lci, &devices);
+    for gpu in gpu_info.iter_mut() {
+        if let Some(mig_instances) = gpu.index.parse::<u32>().ok().and_then(|index| instances.remove(&index)) {
+            gpu.mig_instances = mig_instances;
+        }
+    }
+}
+
+fn nvidia_smi(args: &[&str]) -> Option<String> {
+    match Command::new("nvidia-smi").args(args).output() {
+        Ok(output) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).to_string()),
+        _ => None,
+    }
+}
+
+/// 解析 nvidia-smi mig -lgi、nvidia-smi mig -lci 和 nvidia-smi -L 的输出，按显卡序号分组
+pub fn parse_mig(lgi: &str, lci: &str, devices: &str) -> BTreeMap<u32, Vec<MigInstance>> {
+    let mut instances: BTreeMap<u32, Vec<MigInstance>> = BTreeMap::new();
+
+    for columns in table_rows(lgi) {
+        // GPU | Name | Profile ID | Instance ID | Placement
+        if columns.len() < 5 {
+            continue;
+        }
+        let n = columns.len();
+        let gpu = match columns[0].parse::<u32>() {
+            Ok(gpu) => gpu,
+            Err(_) => continue,
+        };
+        let profile = columns[1..n - 3].join(" ");
+        let memory_slice = memory_slice(&profile);
+        let (placement_start, placement_size) = placement(&columns[n - 1]);
+        instances.entry(gpu).or_default().push(MigInstance {
+            gpu_instance_id: columns[n - 2].parse().unwrap_or_default(),
+            profile_id: columns[n - 3].parse().unwrap_or_default(),
+            memory_bytes: memory_slice_bytes(&memory_slice),
+            profile,
+            memory_slice,
+            placement_start,
+            placement_size,
+            compute_instances: Vec::new(),
+        });
+    }
+
+    for columns in table_rows(lci) {
+        // GPU | GPU Instance ID | Name | Profile ID | Instance ID | Placement
+        if columns.len() < 6 {
+            continue;
+        }
+        let n = columns.len();
+        let (gpu, gpu_instance_id) = match (columns[0].parse::<u32>(), columns[1].parse::<u32>()) {
+            (Ok(gpu), Ok(gpu_instance_id)) => (gpu, gpu_instance_id),
+            _ => continue,
+        };
+        let instance = instances
+            .get_mut(&gpu)
+            .and_then(|instances| instances.iter_mut().find(|instance| instance.gpu_instance_id == gpu_instance_id));
+        if let Some(instance) = instance {
+            let (placement_start, placement_size) = placement(&columns[n - 1]);
+            instance.compute_instances.push(MigComputeInstance {
+                compute_instance_id: columns[n - 2].parse().unwrap_or_default(),
+                profile_id: columns[n - 3].parse().unwrap_or_default(),
+                profile: columns[2..n - 3].join(" "),
+                placement_start,
+                placement_size,
+                uuid: String::new(),
+            });
+        }
+    }
+
+    attach_uuids(&mut instances, &parse_mig_devices(devices));
+    instances
+}
+
+/// 旧驱动的 UUID 形如 MIG-GPU-<uuid>/<gi>/<ci>，可以直接对上实例；
+/// 新驱动的 UUID 不带实例号，按 nvidia-smi -L 与 -lci 相同的列出顺序对应，profile 对不上时不填
+fn attach_uuids(instances: &mut BTreeMap<u32, Vec<MigInstance>>, devices: &[MigDevice]) {
+    for (gpu, instances) in instances.iter_mut() {
+        let gpu_devices: Vec<&MigDevice> = devices.iter().filter(|device| device.gpu == *gpu).collect();
+        let compute_instances = instances.iter_mut().flat_map(|instance| {
+            let gpu_instance_id = instance.gpu_instance_id;
+            instance.compute_instances.iter_mut().map(move |compute| (gpu_instance_id, compute))
+        });
+
+        for (position, (gpu_instance_id, compute)) in compute_instances.enumerate() {
+            let legacy = gpu_devices.iter().find(|device| {
+                legacy_instance_ids(&device.uuid) == Some((gpu_instance_id, compute.compute_instance_id))
+            });
+            let device = legacy.or_else(|| {
+                gpu_devices
+                    .get(position)
+                    .filter(|device| legacy_instance_ids(&device.uuid).is_none() && device.profile == compute.profile)
+            });
+            if let Some(device) = device {
+                compute.uuid = device.uuid.clone();
+            }
+        }
+    }
+}
+
+fn legacy_instance_ids(uuid: &str) -> Option<(u32, u32)> {
+    let mut parts = uuid.strip_prefix("MIG-GPU-")?.split('/').skip(1);
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+/// 解析 nvidia-smi -L：
+/// GPU 0: NVIDIA A100-SXM4-40GB (UUID: GPU-...)
+///   MIG 1g.5gb      Device  0: (UUID: MIG-...)
+fn parse_mig_devices(output: &str) -> Vec<MigDevice> {
+    let mut devices = Vec::new();
+    let mut gpu = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("GPU ") {
+            gpu = rest.split(':').next().and_then(|index| index.trim().parse::<u32>().ok());
+            continue;
+        }
+        if !line.starts_with("MIG ") {
+            continue;
+        }
+
+        let (gpu, device) = match (gpu, line.find("Device")) {
+            (Some(gpu), Some(device)) => (gpu, device),
+            _ => continue,
+        };
+        let uuid = match line.find("UUID:") {
+            Some(start) => line[start + 5..].trim().trim_end_matches(')').trim(),
+            None => continue,
+        };
+        devices.push(MigDevice {
+            gpu,
+            profile: line[..device].split_whitespace().collect::<Vec<_>>().join(" "),
+            uuid: uuid.to_string(),
+        });
+    }
+
+    devices
+}
+
+/// nvidia-smi mig 表格中的数据行，按空白切分
+fn table_rows(output: &str) -> Vec<Vec<String>> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix('|')?.strip_suffix('|'))
+        .map(|line| line.split_whitespace().map(|s| s.to_string()).collect::<Vec<_>>())
+        .filter(|columns| columns.last().is_some_and(|last| last.contains(':')))
+        .collect()
+}
+
+fn placement(value: &str) -> (u32, u32) {
+    let mut parts = value.split(':');
+    let start = parts.next().and_then(|start| start.parse().ok()).unwrap_or_default();
+    let size = parts.next().and_then(|size| size.parse().ok()).unwrap_or_default();
+    (start, size)
+}
+
+/// "MIG 3g.20gb" -> "20gb"，"MIG 1g.10gb+me" -> "10gb"
+fn memory_slice(profile: &str) -> String {
+    profile
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .split('+')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn memory_slice_bytes(memory_slice: &str) -> Option<u64> {
+    let gigabytes = memory_slice.strip_suffix("gb")?.parse::<u64>().ok()?;
+    Some(gigabytes << 30)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LGI: &str = "\
++-------------------------------------------------------+
+| GPU instances:                                        |
+| GPU   Name             Profile  Instance   Placement  |
+|                          ID       ID       Start:Size |
+|=======================================================|
+|   0  MIG 1g.5gb          19        7          4:1     |
++-------------------------------------------------------+
+|   0  MIG 1g.5gb          19        8          5:1     |
++-------------------------------------------------------+
+|   0  MIG 2g.10gb         14        3          2:2     |
++-------------------------------------------------------+
+|   1  MIG 1g.10gb+me      20       13          6:1     |
++-------------------------------------------------------+
+";
+
+    const LCI: &str = "\
++--------------------------------------------------------------------+
+| Compute instances:                                                 |
+| GPU     GPU       Name             Profile   Instance   Placement  |
+|       Instance                       ID        ID       Start:Size |
+|         ID                                                         |
+|====================================================================|
+|   0      7       MIG 1g.5gb           0         0          0:1     |
++--------------------------------------------------------------------+
+|   0      8       MIG 1g.5gb           0         0          0:1     |
++--------------------------------------------------------------------+
+|   0      3       MIG 2g.10gb          1         0          0:2     |
++--------------------------------------------------------------------+
+|   1     13       MIG 1g.10gb          0         0          0:1     |
++--------------------------------------------------------------------+
+";
+
+    // R470 之后的驱动，UUID 不带实例号，按列出顺序对应
+    const LIST: &str = "\
+GPU 0: NVIDIA A100-SXM4-40GB (UUID: GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77)
+  MIG 1g.5gb      Device  0: (UUID: MIG-c6d4f1ef-42e4-5de3-91c7-45d71c87eb3f)
+  MIG 1g.5gb      Device  1: (UUID: MIG-cba663e8-9bed-5b25-b243-5985ef7c9beb)
+  MIG 2g.10gb     Device  2: (UUID: MIG-1d4fbb2b-5c3a-5e4d-8f7a-2b1c0d9e8f7a)
+GPU 1: NVIDIA A100-SXM4-80GB (UUID: GPU-7c1e2d3f-4a5b-6c7d-8e9f-0a1b2c3d4e5f)
+  MIG 1g.10gb     Device  0: (UUID: MIG-0f1e2d3c-4b5a-5968-8776-a5b4c3d2e1f0)
+";
+
+    // R450 驱动，UUID 形如 MIG-GPU-<uuid>/<gi>/<ci>，列出顺序与 -lci 不同
+    const LEGACY_LIST: &str = "\
+GPU 0: A100-SXM4-40GB (UUID: GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77)
+  MIG 2g.10gb Device 0: (UUID: MIG-GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77/3/0)
+  MIG 1g.5gb Device 1: (UUID: MIG-GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77/7/0)
+  MIG 1g.5gb Device 2: (UUID: MIG-GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77/8/0)
+";
+
+    #[test]
+    fn parses_instances() {
+        let instances = parse_mig(LGI, LCI, "");
+        assert_eq!(instances.keys().copied().collect::<Vec<u32>>(), vec![0, 1]);
+
+        let gpu0 = &instances[&0];
+        assert_eq!(gpu0.len(), 3);
+        assert_eq!(gpu0[0].gpu_instance_id, 7);
+        assert_eq!(gpu0[0].profile_id, 19);
+        assert_eq!(gpu0[0].profile, "MIG 1g.5gb");
+        assert_eq!(gpu0[0].memory_slice, "5gb");
+        assert_eq!(gpu0[0].memory_bytes, Some(5 << 30));
+        assert_eq!((gpu0[0].placement_start, gpu0[0].placement_size), (4, 1));
+        assert_eq!(gpu0[2].gpu_instance_id, 3);
+        assert_eq!((gpu0[2].placement_start, gpu0[2].placement_size), (2, 2));
+
+        let compute = &gpu0[2].compute_instances;
+        assert_eq!(compute.len(), 1);
+        assert_eq!(compute[0].profile, "MIG 2g.10gb");
+        assert_eq!(compute[0].profile_id, 1);
+        assert_eq!((compute[0].placement_start, compute[0].placement_size), (0, 2));
+        // 没有 -L 的输出时不填 UUID
+        assert_eq!(compute[0].uuid, "");
+
+        let gpu1 = &instances[&1];
+        assert_eq!(gpu1[0].profile, "MIG 1g.10gb+me");
+        assert_eq!(gpu1[0].memory_slice, "10gb");
+        assert_eq!(gpu1[0].compute_instances[0].compute_instance_id, 0);
+    }
+
+    #[test]
+    fn matches_uuids_by_position() {
+        let instances = parse_mig(LGI, LCI, LIST);
+        let uuids: Vec<&str> = instances[&0]
+            .iter()
+            .map(|instance| instance.compute_instances[0].uuid.as_str())
+            .collect();
+        assert_eq!(uuids, vec![
+            "MIG-c6d4f1ef-42e4-5de3-91c7-45d71c87eb3f",
+            "MIG-cba663e8-9bed-5b25-b243-5985ef7c9beb",
+            "MIG-1d4fbb2b-5c3a-5e4d-8f7a-2b1c0d9e8f7a",
+        ]);
+        assert_eq!(instances[&1][0].compute_instances[0].uuid, "MIG-0f1e2d3c-4b5a-5968-8776-a5b4c3d2e1f0");
+    }
+
+    #[test]
+    fn position_match_requires_same_profile() {
+        // 列出顺序与 -lci 不一致时宁可不填，也不填错
+        let list = "\
+GPU 0: NVIDIA A100-SXM4-40GB (UUID: GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77)
+  MIG 2g.10gb     Device  0: (UUID: MIG-1d4fbb2b-5c3a-5e4d-8f7a-2b1c0d9e8f7a)
+  MIG 1g.5gb      Device  1: (UUID: MIG-c6d4f1ef-42e4-5de3-91c7-45d71c87eb3f)
+  MIG 1g.5gb      Device  2: (UUID: MIG-cba663e8-9bed-5b25-b243-5985ef7c9beb)
+";
+        let instances = parse_mig(LGI, LCI, list);
+        let uuids: Vec<&str> = instances[&0]
+            .iter()
+            .map(|instance| instance.compute_instances[0].uuid.as_str())
+            .collect();
+        assert_eq!(uuids, vec!["", "MIG-c6d4f1ef-42e4-5de3-91c7-45d71c87eb3f", ""]);
+    }
+
+    #[test]
+    fn matches_legacy_uuids_by_instance_id() {
+        let instances = parse_mig(LGI, LCI, LEGACY_LIST);
+        let uuid = |gi: usize| instances[&0][gi].compute_instances[0].uuid.clone();
+        assert_eq!(uuid(0), "MIG-GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77/7/0");
+        assert_eq!(uuid(1), "MIG-GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77/8/0");
+        assert_eq!(uuid(2), "MIG-GPU-5d5ba0d6-d33d-2b2c-524d-9e3d8d2b8a77/3/0");
+    }
+
+    #[test]
+    fn mig_disabled() {
+        let lgi = "No MIG-enabled devices found.\n";
+        assert!(parse_mig(lgi, "", "").is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::GpuInfo;
+use crate::pci::{self, PciAddress, PciDevice};
+use crate::sysfs;
+
+mod nvidia;
+mod nvidia_xml;
+mod mig;
+mod nvml;
+mod nvidia_pci;
+mod amd;
+mod huawei;
+mod intel;
+mod process;
+mod control;
+mod sampler;
+mod topology;
+
+pub use nvidia::{nvidia, parse_nvidia_csv, NvidiaSmi};
+pub use nvidia_xml::{GpuDetails, parse_nvidia_smi_xml};
+pub use mig::{MigInstance, MigComputeInstance, parse_mig};
+pub use nvml::NvmlProbe;
+pub use nvidia_pci::{NvidiaPci, nvidia_pci};
+pub use amd::AmdSysfs;
+pub use huawei::{AscendNpuSmi, parse_npu_smi_info, npu_smi_value};
+pub use intel::IntelSysfs;
+pub use topology::{GpuAffinity, GpuTopology, LinkType, gpu_topology, parse_topo_matrix};
+pub use sampler::{GpuSampler, GpuStats, SampleStats, start_gpu_sampler, gpu_stats};
+pub use control::{CommandRunner, SystemRunner, GpuControl, GpuTarget, GpuSetting, GpuChange, ComputeMode};
+pub use process::{GpuProcess, gpu_processes, parse_compute_apps, resolve_containers, container_id_from_cgroup};
+
+/// 显卡后端，每个厂商（或者同一厂商的不同获取方式）实现一个
+pub trait GpuProbe: Send + Sync {
+    /// 后端名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 厂商标识，写入 GpuInfo.vendor，同一厂商只取第一个成功的后端
+    fn vendor(&self) -> &'static str;
+
+    /// 根据 PCI 设备列表判断本机是否有该后端支持的设备
+    fn detect(&self, devices: &[PciDevice]) -> bool;
+
+    /// 列出所有设备
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>>;
+
+    /// 采集利用率、显存、温度、功耗等动态数据，后台采样时定时调用，
+    /// 只需要填这几项和用于识别显卡的序号、uuid、总线地址；默认重新枚举一次
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        self.enumerate()
+    }
+}
+
+static REGISTRY: OnceLock<Arc<GpuRegistry>> = OnceLock::new();
+
+/// 按顺序排列的显卡后端
+pub struct GpuRegistry {
+    probes: Vec<Box<dyn GpuProbe>>,
+    /// 第一次调用时扫描的 PCI 设备，采样时不再重复扫描
+    devices: OnceLock<Vec<PciDevice>>,
+}
+
+impl Default for GpuRegistry {
+    fn default() -> Self {
+        GpuRegistry::empty()
+            .register(NvmlProbe)
+            .register(NvidiaSmi)
+            .register(NvidiaPci::default())
+            .register(AmdSysfs::default())
+            .register(AscendNpuSmi)
+            .register(IntelSysfs::default())
+    }
+}
+
+impl GpuRegistry {
+    pub fn empty() -> Self {
+        GpuRegistry {
+            probes: vec![],
+            devices: OnceLock::new(),
+        }
+    }
+
+    /// 进程内共用的默认后端列表，上报和后台采样都用它；
+    /// Intel 的功耗要靠两次读数的能量差计算，各后端的状态需要跨调用保留
+    pub fn shared() -> Arc<GpuRegistry> {
+        REGISTRY.get_or_init(|| Arc::new(GpuRegistry::default())).clone()
+    }
+
+    /// 追加一个后端，同一厂商的后端按注册顺序尝试
+    pub fn register<P: GpuProbe + 'static>(mut self, probe: P) -> Self {
+        self.probes.push(Box::new(probe));
+        self
+    }
+
+    pub fn probes(&self) -> &[Box<dyn GpuProbe>] {
+        &self.probes
+    }
+
+    /// 本机的 PCI 设备，只在第一次调用时扫描
+    pub fn devices(&self) -> &[PciDevice] {
+        self.devices.get_or_init(pci::scan)
+    }
+
+    /// 是否有 N 卡，没有时不需要执行 nvidia-smi
+    pub fn has_nvidia(&self) -> bool {
+        self.devices().iter().any(|device| device.vendor_id == 0x10de && device.is_display())
+    }
+
+    /// 依次调用各个后端并合并结果，每个厂商只使用第一个成功的后端
+    pub fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        self.collect(|probe| probe.enumerate())
+    }
+
+    /// 与 enumerate 相同，但调用各后端的 sample
+    pub fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        self.collect(|probe| probe.sample())
+    }
+
+    fn collect<F>(&self, run: F) -> Result<Vec<GpuInfo>, Box<dyn Error>>
+    where
+        F: Fn(&dyn GpuProbe) -> Result<Vec<GpuInfo>, Box<dyn Error>>,
+    {
+        let devices = self.devices();
+
+        let mut vendors: Vec<&str> = vec![];
+        let mut gpu_info: Vec<GpuInfo> = vec![];
+        let mut last_err: Option<Box<dyn Error>> = None;
+
+        // 同一个设备被多个后端报告时只保留第一个后端的，按 PCI 地址比较而不是按序号；
+        // 同一后端内不去重，310P 等一卡多芯片的设备共用一个地址
+        let mut seen: HashSet<PciAddress> = HashSet::new();
+
+        for probe in &self.probes {
+            if vendors.contains(&probe.vendor()) || !probe.detect(devices) {
+                continue;
+            }
+
+            match run(probe.as_ref()) {
+                Ok(list) => {
+                    info!("{}: 获取到 {} 张显卡", probe.name(), list.len());
+                    vendors.push(probe.vendor());
+                    let list: Vec<GpuInfo> = list
+                        .into_iter()
+                        .filter(|gpu| !matches!(known_address(gpu), Some(address) if seen.contains(&address)))
+                        .collect();
+                    seen.extend(list.iter().filter_map(known_address));
+                    gpu_info.extend(list.into_iter().map(|mut gpu| {
+                        gpu.vendor = probe.vendor().to_string();
+                        gpu
+                    }));
+                }
+                Err(err) => {
+                    info!("{}: 获取显卡信息失败: {}", probe.name(), err);
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        match last_err {
+            Some(err) if gpu_info.is_empty() => Err(err),
+            _ => Ok(gpu_info),
+        }
+    }
+}
+
+/// 各后端支持的显卡：N 卡、A 卡、Intel 的显示控制器和昇腾 NPU；
+/// BMC 自带的 ASPEED、Hi1710 等显示芯片不算
+pub(crate) fn is_supported_gpu(device: &PciDevice) -> bool {
+    match device.vendor_id {
+        0x10de | 0x1002 | 0x8086 => device.is_display(),
+        // 华为服务器的 iBMC（Hi1710）也是 0x19e5 的 VGA 设备，只认处理加速器
+        0x19e5 => device.is_accelerator(),
+        _ => false,
+    }
+}
+
+/// 显卡的 PCI 地址，Windows 上 wmic 拿不到地址，全 0 的地址视为未知，不参与去重
+fn known_address(gpu: &GpuInfo) -> Option<PciAddress> {
+    gpu.pci_address().filter(|address| *address != PciAddress::default())
+}
+
+/// drm 目录下指定 PCI 厂商的显卡，返回各个 cardN/device 目录
+pub(crate) fn drm_cards(root: &Path, vendor_id: u32) -> Vec<PathBuf> {
+    sysfs::numbered_entries(root, "card")
+        .into_iter()
+        .map(|(_, card)| card.join("device"))
+        .filter(|device| sysfs::read_hex(device.join("vendor")) == Some(vendor_id))
+        .collect()
+}
+
+/// 设备的第一个 hwmon 目录，温度、功耗都在这里
+pub(crate) fn hwmon_dir(device: &Path) -> Option<PathBuf> {
+    sysfs::numbered_entries(device.join("hwmon"), "hwmon")
+        .into_iter()
+        .next()
+        .map(|(_, hwmon)| hwmon)
+}
+
+/// 拆分 nvidia-smi 的数值和单位，如 "75.20 W" -> (75.2, "W")
+/// "[N/A]"、"N/A"、"[Not Supported]" 等返回 None
+pub fn parse_quantity(value: &str) -> Option<(f64, &str)> {
+    let value = value.trim().trim_start_matches('[').trim_end_matches(']').trim();
+    let (number, unit) = match value.find(|c: char| c.is_whitespace()) {
+        Some(pos) => (&value[..pos], value[pos..].trim()),
+        None => (value, ""),
+    };
+    number.parse::<f64>().ok().map(|number| (number, unit))
+}
+
+/// 显存大小转为字节，没有单位时按 MiB 处理
+pub fn parse_memory_bytes(value: &str) -> Option<u64> {
+    let (number, unit) = parse_quantity(value)?;
+    let scale: u64 = match unit {
+        "B" => 1,
+        "KiB" | "KB" => 1 << 10,
+        "" | "MiB" | "MB" => 1 << 20,
+        "GiB" | "GB" => 1 << 30,
+        _ => return None,
+    };
+    Some((number * scale as f64) as u64)
+}
+
+/// 旧版字符串格式的显存，与 nvidia-smi 的输出一致
+pub fn legacy_memory(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) => format!("{} MiB", bytes >> 20),
+        None => "[N/A]".to_string(),
+    }
+}
+
+/// 旧版字符串格式的温度
+pub fn legacy_temperature(celsius: Option<f32>) -> String {
+    match celsius {
+        Some(celsius) => format!("{}", celsius.round() as i64),
+        None => "[N/A]".to_string(),
+    }
+}
+
+/// 旧版字符串格式的功耗
+pub fn legacy_power(watts: Option<f32>) -> String {
+    match watts {
+        Some(watts) => format!("{:.2} W", watts),
+        None => "[N/A]".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 返回固定结果的后端
+    struct FakeProbe {
+        vendor: &'static str,
+        bus_ids: Vec<&'static str>,
+    }
+
+    impl GpuProbe for FakeProbe {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn vendor(&self) -> &'static str {
+            self.vendor
+        }
+
+        fn detect(&self, _devices: &[PciDevice]) -> bool {
+            true
+        }
+
+        fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+            Ok(self.bus_ids.iter().enumerate().map(|(index, bus_id)| GpuInfo {
+                index: index.to_string(),
+                gpu_bus_id: bus_id.to_string(),
+                ..Default::default()
+            }).collect())
+        }
+    }
+
+    #[test]
+    fn dedups_across_backends_only() {
+        let registry = GpuRegistry::empty()
+            .register(FakeProbe { vendor: "a", bus_ids: vec!["0000:01:00.0", "0000:01:00.0", "0000:02:00.0"] })
+            .register(FakeProbe { vendor: "b", bus_ids: vec!["00000000:02:00.0", "0000:03:00.0"] });
+        let bus_ids: Vec<String> = registry.enumerate().unwrap().into_iter().map(|gpu| gpu.gpu_bus_id).collect();
+        assert_eq!(bus_ids, vec!["0000:01:00.0", "0000:01:00.0", "0000:02:00.0", "0000:03:00.0"]);
+    }
+
+    #[test]
+    fn unknown_addresses_are_kept() {
+        // Windows 上 wmic 拿不到地址
+        let registry = GpuRegistry::empty()
+            .register(FakeProbe { vendor: "a", bus_ids: vec!["00000000:00:00.0", ""] })
+            .register(FakeProbe { vendor: "b", bus_ids: vec!["00000000:00:00.0", ""] });
+        assert_eq!(registry.enumerate().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn has_nvidia_needs_display_device() {
+        let registry = GpuRegistry::empty();
+        registry.devices.set(vec![
+            // ConnectX 网卡和 NVSwitch 之类的桥接设备不算
+            PciDevice { vendor_id: 0x15b3, class: 0x020000, ..Default::default() },
+            PciDevice { vendor_id: 0x10de, class: 0x068000, ..Default::default() },
+        ]).unwrap();
+        assert!(!registry.has_nvidia());
+
+        let registry = GpuRegistry::empty();
+        registry.devices.set(vec![PciDevice { vendor_id: 0x10de, class: 0x030200, ..Default::default() }]).unwrap();
+        assert!(registry.has_nvidia());
+    }
+
+    #[test]
+    fn shared_registry_is_reused() {
+        assert!(Arc::ptr_eq(&GpuRegistry::shared(), &GpuRegistry::shared()));
+    }
+
+    /// 只有 energy1_input 的 Intel 显卡，同一个 registry 第二次枚举才有功耗
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn energy_power_needs_long_lived_registry() {
+        use std::fs;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let device = root.join("devices/0000:03:00.0");
+        let hwmon = device.join("hwmon/hwmon2");
+        fs::create_dir_all(&hwmon).unwrap();
+        fs::create_dir_all(root.join("drivers/xe")).unwrap();
+        fs::create_dir_all(root.join("card1")).unwrap();
+        fs::write(device.join("vendor"), "0x8086\n").unwrap();
+        fs::write(device.join("device"), "0xe20b\n").unwrap();
+        std::os::unix::fs::symlink(root.join("drivers/xe"), device.join("driver")).unwrap();
+        std::os::unix::fs::symlink(&device, root.join("card1/device")).unwrap();
+        fs::write(hwmon.join("energy1_input"), "5000000000\n").unwrap();
+
+        let registry = GpuRegistry::empty().register(IntelSysfs::with_root(root));
+        registry.devices.set(vec![PciDevice {
+            address: PciAddress::parse("0000:03:00.0").unwrap(),
+            class: 0x030000,
+            vendor_id: 0x8086,
+            device_id: 0xe20b,
+            driver: Some("xe".to_string()),
+            ..Default::default()
+        }]).unwrap();
+
+        let first = registry.enumerate().unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].power_draw_watts, None);
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(hwmon.join("energy1_input"), "5002000000\n").unwrap();
+        let second = registry.enumerate().unwrap();
+        assert!(second[0].power_draw_watts.is_some_and(|watts| watts > 0.0));
+        assert_ne!(second[0].power_draw, "[N/A]");
+    }
+
+    #[test]
+    fn parses_quantities() {
+        assert_eq!(parse_quantity("75.20 W"), Some((75.2, "W")));
+        assert_eq!(parse_quantity("[N/A]"), None);
+        assert_eq!(parse_quantity("[Not Supported]"), None);
+        assert_eq!(parse_memory_bytes("2 GiB"), Some(2 << 30));
+        assert_eq!(parse_memory_bytes("512"), Some(512 << 20));
+        assert_eq!(parse_memory_bytes("12 W"), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::error::Error;
+
+use crate::GpuInfo;
+use crate::pci::PciDevice;
+use super::{GpuProbe, parse_memory_bytes, parse_quantity, parse_nvidia_smi_xml};
+use super::mig::attach_mig_instances;
+
+/// 通过 nvidia-smi 获取 N 卡信息
+pub struct NvidiaSmi;
+
+const QUERY_GPU: &str = "index,name,uuid,gpu_bus_id,memory.used,memory.total,temperature.gpu,power.draw";
+
+impl GpuProbe for NvidiaSmi {
+    fn name(&self) -> &'static str {
+        "nvidia-smi"
+    }
+
+    fn vendor(&self) -> &'static str {
+        "nvidia"
+    }
+
+    fn detect(&self, devices: &[PciDevice]) -> bool {
+        devices.iter().any(|device| device.vendor_id == 0x10de && device.is_display())
+    }
+
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        let mut gpu_info = query_gpu(QUERY_GPU)?;
+        attach_details(&mut gpu_info);
+        attach_mig_instances(&mut gpu_info);
+        Ok(gpu_info)
+    }
+
+    /// 只跑一次 --query-gpu，多查一列利用率，不读 -q -x 和 MIG
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        query_gpu(&format!("{},utilization.gpu", QUERY_GPU))
+    }
+}
+
+fn query_gpu(fields: &str) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+    let output = match std::process::Command::new("nvidia-smi")
+    .arg(format!("--query-gpu={}", fields))
+    .arg("--format=csv,noheader")
+    .output() {
+        Ok(output) => output,
+        Err(_) => {
+            return Err("nvidia-smi 执行失败".into());
+        },
+    };
+
+    if output.status.success() {
+        let output = String::from_utf8_lossy(&output.stdout);
+
+        if output.contains("NVIDIA-SMI has failed") {
+            return Err("nvidia-smi 执行失败".into());
+        }
+
+        return Ok(parse_nvidia_csv(&output));
+    }
+
+    Err("nvidia-smi 执行失败".into())
+}
+
+/// 读取 nvidia-smi -q -x 并按 uuid 附加到对应的显卡上，失败时不影响基本信息
+fn attach_details(gpu_info: &mut [GpuInfo]) {
+    let output = match std::process::Command::new("nvidia-smi").arg("-q").arg("-x").output() {
+        Ok(output) if output.status.success() => output,
+        _ => {
+            info!("nvidia-smi -q -x 执行失败");
+            return;
+        }
+    };
+
+    let details = match parse_nvidia_smi_xml(&String::from_utf8_lossy(&output.stdout)) {
+        Ok(details) => details,
+        Err(err) => {
+            info!("nvidia-smi -q -x 解析失败: {}", err);
+            return;
+        }
+    };
+
+    for gpu in gpu_info.iter_mut() {
+        gpu.details = details.iter().find(|details| details.uuid == gpu.uuid).cloned();
+        if let Some(details) = &gpu.details {
+            gpu.utilization_percent = details.gpu_utilization_percent;
+            gpu.clock_mhz = details.sm_clock_mhz;
+            gpu.max_clock_mhz = details.max_sm_clock_mhz;
+        }
+    }
+}
+
+pub async fn nvidia() -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+    crate::blocking(|| NvidiaSmi.enumerate()).await
+}
+
+/// 解析 --query-gpu=index,name,uuid,gpu_bus_id,memory.used,memory.total,temperature.gpu,power.draw
+/// --format=csv,noheader 的输出，采样时末尾多一列 utilization.gpu
+pub fn parse_nvidia_csv(output: &str) -> Vec<GpuInfo> {
+    split_gpu_info(output)
+        .into_iter()
+        .filter(|info| info.len() >= 8)
+        .map(|info| GpuInfo {
+            memory_used_bytes: parse_memory_bytes(&info[4]),
+            memory_total_bytes: parse_memory_bytes(&info[5]),
+            temperature_celsius: parse_quantity(&info[6]).map(|(value, _)| value as f32),
+            power_draw_watts: parse_quantity(&info[7]).map(|(value, _)| value as f32),
+            utilization_percent: info.get(8).and_then(|value| parse_quantity(value)).map(|(value, _)| value as f32),
+            index: info[0].clone(),
+            name: info[1].clone(),
+            uuid: info[2].clone(),
+            gpu_bus_id: info[3].clone(),
+            memory_used: info[4].clone(),
+            memory_total: info[5].clone(),
+            temperature: info[6].clone(),
+            power_draw: info[7].clone(),
+            vendor: "nvidia".to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn split_gpu_info(gpu_info: &str) -> Vec<Vec<String>> {
+    gpu_info
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|s| s.trim().to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // nvidia-smi --query-gpu=index,name,uuid,gpu_bus_id,memory.used,memory.total,temperature.gpu,power.draw --format=csv,noheader
+    const A100_RTX: &str = "\
+0, NVIDIA A100-SXM4-80GB, GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11, 00000000:07:00.0, 1024 MiB, 81920 MiB, 34, 61.38 W
+1, NVIDIA GeForce RTX 4090, GPU-0b7d3f52-1c8e-4a67-9d21-7e5c3a8b6f90, 00000000:3B:00.0, 0 MiB, 24564 MiB, 41, [N/A]
+";
+
+    // 数据中心卡开启 MIG、笔记本独显等场景下部分字段不可用
+    const UNSUPPORTED: &str = "\
+0, NVIDIA H100 80GB HBM3, GPU-9c1f6e4b-2a7d-4e3c-b5f8-0d1a2c3e4f56, 00000000:1A:00.0, [N/A], [N/A], [N/A], [Not Supported]
+1, Tesla T4, GPU-3d4e5f60-7182-4394-a5b6-c7d8e9f0a1b2, 00000000:AF:00.0, 512 MiB, 15360 MiB, [Not Supported], 27.10 W
+";
+
+    #[test]
+    fn parses_full_rows() {
+        let gpus = parse_nvidia_csv(A100_RTX);
+        assert_eq!(gpus.len(), 2);
+
+        let a100 = &gpus[0];
+        assert_eq!(a100.index, "0");
+        assert_eq!(a100.name, "NVIDIA A100-SXM4-80GB");
+        assert_eq!(a100.uuid, "GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11");
+        assert_eq!(a100.gpu_bus_id, "00000000:07:00.0");
+        assert_eq!(a100.memory_used_bytes, Some(1024 << 20));
+        assert_eq!(a100.memory_total_bytes, Some(81920 << 20));
+        assert_eq!(a100.temperature_celsius, Some(34.0));
+        assert_eq!(a100.power_draw_watts, Some(61.38));
+        assert_eq!(a100.vendor, "nvidia");
+        // 旧字段保留原始字符串
+        assert_eq!(a100.memory_total, "81920 MiB");
+        assert_eq!(a100.power_draw, "61.38 W");
+
+        let rtx = &gpus[1];
+        assert_eq!(rtx.memory_used_bytes, Some(0));
+        assert_eq!(rtx.temperature_celsius, Some(41.0));
+        assert_eq!(rtx.power_draw_watts, None);
+        assert_eq!(rtx.power_draw, "[N/A]");
+    }
+
+    #[test]
+    fn not_available_fields_are_none() {
+        let gpus = parse_nvidia_csv(UNSUPPORTED);
+        assert_eq!(gpus.len(), 2);
+
+        let h100 = &gpus[0];
+        assert_eq!(h100.memory_used_bytes, None);
+        assert_eq!(h100.memory_total_bytes, None);
+        assert_eq!(h100.temperature_celsius, None);
+        assert_eq!(h100.power_draw_watts, None);
+        assert_eq!(h100.power_draw, "[Not Supported]");
+
+        let t4 = &gpus[1];
+        assert_eq!(t4.memory_total_bytes, Some(15360 << 20));
+        assert_eq!(t4.temperature_celsius, None);
+        assert_eq!(t4.power_draw_watts, Some(27.1));
+    }
+
+    #[test]
+    fn parses_sample_rows() {
+        let output = "\
+0, NVIDIA A100-SXM4-80GB, GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11, 00000000:07:00.0, 40960 MiB, 81920 MiB, 58, 312.44 W, 97 %
+1, NVIDIA A100-SXM4-80GB, GPU-0b7d3f52-1c8e-4a67-9d21-7e5c3a8b6f90, 00000000:0F:00.0, 0 MiB, 81920 MiB, 33, 60.12 W, [N/A]
+";
+        let gpus = parse_nvidia_csv(output);
+        assert_eq!(gpus[0].utilization_percent, Some(97.0));
+        assert_eq!(gpus[0].memory_used_bytes, Some(40960 << 20));
+        assert_eq!(gpus[1].utilization_percent, None);
+        // 不带利用率列时为空
+        assert_eq!(parse_nvidia_csv(A100_RTX)[0].utilization_percent, None);
+    }
+
+    #[test]
+    fn skips_short_and_blank_rows() {
+        let output = "\
+0, NVIDIA A10, GPU-11111111-2222-3333-4444-555555555555, 00000000:17:00.0, 0 MiB, 23028 MiB, 30, 15.42 W
+
+1, NVIDIA A10, GPU-66666666-7777-8888-9999-000000000000, 00000000:31:00.0, 0 MiB
+No devices were found
+";
+        let gpus = parse_nvidia_csv(output);
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].gpu_bus_id, "00000000:17:00.0");
+    }
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::sync::Mutex;
+
+use crate::GpuInfo;
+use crate::pci::{PciAddress, PciDevice};
+use super::GpuProbe;
+
+const NVIDIA_VENDOR_ID: u16 = 0x10de;
+
+/// nvidia-smi 不可用（没装驱动）时，至少从 PCI 设备中列出 N 卡型号
+#[derive(Default)]
+pub struct NvidiaPci {
+    /// detect 时注册表传入的 PCI 设备，enumerate 直接使用，不再重新扫描
+    devices: Mutex<Vec<PciDevice>>,
+}
+
+impl GpuProbe for NvidiaPci {
+    fn name(&self) -> &'static str {
+        "nvidia-pci"
+    }
+
+    fn vendor(&self) -> &'static str {
+        "nvidia"
+    }
+
+    fn detect(&self, devices: &[PciDevice]) -> bool {
+        let gpus: Vec<PciDevice> = devices.iter().filter(|device| is_nvidia_gpu(device)).cloned().collect();
+        let found = !gpus.is_empty();
+        if let Ok(mut devices) = self.devices.lock() {
+            *devices = gpus;
+        }
+        found
+    }
+
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        match self.devices.lock() {
+            Ok(devices) => Ok(nvidia_pci(&devices)),
+            Err(_) => Err("nvidia-pci 设备列表不可用".into()),
+        }
+    }
+
+    /// PCI 上读不到利用率、温度等动态数据，采样时不输出，也省得每次查 pci.ids
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        Ok(vec![])
+    }
+}
+
+fn is_nvidia_gpu(device: &PciDevice) -> bool {
+    device.vendor_id == NVIDIA_VENDOR_ID && device.is_display()
+}
+
+/// 从 PCI 设备列表中取出 N 卡，只有型号和总线地址
+pub fn nvidia_pci(devices: &[PciDevice]) -> Vec<GpuInfo> {
+    devices
+        .iter()
+        .filter(|device| is_nvidia_gpu(device))
+        .enumerate()
+        .map(|(i, device)| GpuInfo {
+            index: i.to_string(),
+            name: device.model(),
+            // Windows 上 wmic 拿不到总线地址，留空而不是写成全 0
+            gpu_bus_id: if device.address == PciAddress::default() {
+                "".to_string()
+            } else {
+                device.address.nvidia_smi()
+            },
+            vendor: "nvidia".to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
@@ -0,0 +1,376 @@
+use serde::{Serialize, Deserialize};
+use std::error::Error;
+
+use super::parse_quantity;
+
+/// `nvidia-smi -q -x` 中的详细信息，nvidia-smi 以外的后端没有这些数据
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct GpuDetails {
+    /// 与 GpuInfo.uuid 对应
+    pub uuid: String,
+    /// 与 GpuInfo.gpu_bus_id 对应，nvidia-smi 的写法
+    pub gpu_bus_id: String,
+    pub driver_version: Option<String>,
+    pub cuda_version: Option<String>,
+    pub vbios_version: Option<String>,
+    pub serial: Option<String>,
+
+    pub gpu_utilization_percent: Option<f32>,
+    pub memory_utilization_percent: Option<f32>,
+    pub encoder_utilization_percent: Option<f32>,
+    pub decoder_utilization_percent: Option<f32>,
+
+    pub sm_clock_mhz: Option<u32>,
+    pub max_sm_clock_mhz: Option<u32>,
+    pub memory_clock_mhz: Option<u32>,
+    pub max_memory_clock_mhz: Option<u32>,
+    pub fan_speed_percent: Option<f32>,
+
+    pub power_limit_watts: Option<f32>,
+    pub default_power_limit_watts: Option<f32>,
+    pub min_power_limit_watts: Option<f32>,
+    pub max_power_limit_watts: Option<f32>,
+
+    pub persistence_mode: Option<bool>,
+    /// Default、Exclusive_Process、Prohibited
+    pub compute_mode: Option<String>,
+    pub ecc_mode: Option<bool>,
+
+    pub retired_pages_single_bit: Option<u64>,
+    pub retired_pages_double_bit: Option<u64>,
+    pub retired_pages_pending: Option<bool>,
+    /// 当前生效的降频原因，如 gpu_idle、sw_power_cap、hw_slowdown
+    pub throttle_reasons: Vec<String>,
+}
+
+/// 解析 `nvidia-smi -q -x` 的输出
+///
+/// 兼容不同版本驱动的字段名：
+/// - 530 之前功耗在 power_readings 下，之后在 gpu_power_readings 下，power_limit 改为 current_power_limit
+/// - 535 之后 clocks_throttle_reasons 改名为 clocks_event_reasons
+pub fn parse_nvidia_smi_xml(xml: &str) -> Result<Vec<GpuDetails>, Box<dyn Error>> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let doc = roxmltree::Document::parse_with_options(xml, options)?;
+    let root = doc.root_element();
+
+    let driver_version = text(root, "driver_version");
+    let cuda_version = text(root, "cuda_version");
+
+    Ok(root
+        .children()
+        .filter(|node| node.has_tag_name("gpu"))
+        .map(|gpu| {
+            let power = child(gpu, "gpu_power_readings").or_else(|| child(gpu, "power_readings"));
+            let power_value = |names: &[&str]| {
+                let power = power?;
+                names.iter().find_map(|name| quantity(power, name))
+            };
+
+            let throttle = child(gpu, "clocks_event_reasons").or_else(|| child(gpu, "clocks_throttle_reasons"));
+            let throttle_reasons = match throttle {
+                Some(throttle) => throttle
+                    .children()
+                    .filter(|node| node.is_element() && node.text().map(|t| t.trim()) == Some("Active"))
+                    .map(|node| {
+                        let name = node.tag_name().name();
+                        name.trim_start_matches("clocks_event_reason_")
+                            .trim_start_matches("clocks_throttle_reason_")
+                            .to_string()
+                    })
+                    .collect(),
+                None => vec![],
+            };
+
+            GpuDetails {
+                uuid: text(gpu, "uuid").unwrap_or_default(),
+                gpu_bus_id: gpu.attribute("id").map(|id| id.to_string())
+                    .or_else(|| text(gpu, "pci/pci_bus_id"))
+                    .unwrap_or_default(),
+                driver_version: driver_version.clone(),
+                cuda_version: cuda_version.clone(),
+                vbios_version: text(gpu, "vbios_version"),
+                serial: text(gpu, "serial"),
+
+                gpu_utilization_percent: quantity(gpu, "utilization/gpu_util"),
+                memory_utilization_percent: quantity(gpu, "utilization/memory_util"),
+                encoder_utilization_percent: quantity(gpu, "utilization/encoder_util"),
+                decoder_utilization_percent: quantity(gpu, "utilization/decoder_util"),
+
+                sm_clock_mhz: quantity(gpu, "clocks/sm_clock").map(|value| value as u32),
+                max_sm_clock_mhz: quantity(gpu, "max_clocks/sm_clock").map(|value| value as u32),
+                memory_clock_mhz: quantity(gpu, "clocks/mem_clock").map(|value| value as u32),
+                max_memory_clock_mhz: quantity(gpu, "max_clocks/mem_clock").map(|value| value as u32),
+                fan_speed_percent: quantity(gpu, "fan_speed"),
+
+                power_limit_watts: power_value(&["current_power_limit", "power_limit", "enforced_power_limit"]),
+                default_power_limit_watts: power_value(&["default_power_limit"]),
+                min_power_limit_watts: power_value(&["min_power_limit"]),
+                max_power_limit_watts: power_value(&["max_power_limit"]),
+
+                persistence_mode: enabled(gpu, "persistence_mode"),
+                compute_mode: text(gpu, "compute_mode"),
+                ecc_mode: enabled(gpu, "ecc_mode/current_ecc"),
+
+                retired_pages_single_bit: quantity(gpu, "retired_pages/multiple_single_bit_retirement/retired_count")
+                    .map(|value| value as u64),
+                retired_pages_double_bit: quantity(gpu, "retired_pages/double_bit_retirement/retired_count")
+                    .map(|value| value as u64),
+                retired_pages_pending: text(gpu, "retired_pages/pending_retirement").map(|value| value == "Yes"),
+                throttle_reasons,
+            }
+        })
+        .collect())
+}
+
+/// 按 a/b/c 的路径查找子元素
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, path: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    path.split('/').try_fold(node, |node, name| {
+        node.children().find(|child| child.has_tag_name(name))
+    })
+}
+
+/// 子元素的文本，N/A、Not Supported 等视为没有值
+fn text(node: roxmltree::Node, path: &str) -> Option<String> {
+    let value = child(node, path)?.text()?.trim();
+    match value {
+        "" | "N/A" | "[N/A]" | "Not Supported" | "[Not Supported]" | "Unknown Error" => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// 去掉单位的数值，如 "250.00 W" -> 250.0
+fn quantity(node: roxmltree::Node, path: &str) -> Option<f32> {
+    parse_quantity(&text(node, path)?).map(|(value, _)| value as f32)
+}
+
+fn enabled(node: roxmltree::Node, path: &str) -> Option<bool> {
+    match text(node, path)?.as_str() {
+        "Enabled" => Some(true),
+        "Disabled" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 470 驱动，Tesla V100：power_readings + clocks_throttle_reasons
+    const DRIVER_470: &str = r#"<?xml version="1.0" ?>
+<!DOCTYPE nvidia_smi_log SYSTEM "nvsmi_device_v11.dtd">
+<nvidia_smi_log>
+	<timestamp>Mon Mar  4 10:21:07 2024</timestamp>
+	<driver_version>470.182.03</driver_version>
+	<cuda_version>11.4</cuda_version>
+	<attached_gpus>1</attached_gpus>
+	<gpu id="00000000:3B:00.0">
+		<product_name>Tesla V100-PCIE-32GB</product_name>
+		<persistence_mode>Disabled</persistence_mode>
+		<serial>0323418012345</serial>
+		<uuid>GPU-8a5f1b0e-6c2d-4f3a-9e1b-7d0c2a4b6e81</uuid>
+		<vbios_version>88.00.80.00.01</vbios_version>
+		<pci>
+			<pci_bus_id>00000000:3B:00.0</pci_bus_id>
+		</pci>
+		<fan_speed>N/A</fan_speed>
+		<compute_mode>Default</compute_mode>
+		<utilization>
+			<gpu_util>87 %</gpu_util>
+			<memory_util>41 %</memory_util>
+			<encoder_util>0 %</encoder_util>
+			<decoder_util>0 %</decoder_util>
+		</utilization>
+		<ecc_mode>
+			<current_ecc>Enabled</current_ecc>
+			<pending_ecc>Enabled</pending_ecc>
+		</ecc_mode>
+		<retired_pages>
+			<multiple_single_bit_retirement>
+				<retired_count>2</retired_count>
+			</multiple_single_bit_retirement>
+			<double_bit_retirement>
+				<retired_count>0</retired_count>
+			</double_bit_retirement>
+			<pending_blacklist>No</pending_blacklist>
+			<pending_retirement>No</pending_retirement>
+		</retired_pages>
+		<clocks_throttle_reasons>
+			<clocks_throttle_reason_gpu_idle>Not Active</clocks_throttle_reason_gpu_idle>
+			<clocks_throttle_reason_applications_clocks_setting>Not Active</clocks_throttle_reason_applications_clocks_setting>
+			<clocks_throttle_reason_sw_power_cap>Active</clocks_throttle_reason_sw_power_cap>
+			<clocks_throttle_reason_hw_slowdown>Not Active</clocks_throttle_reason_hw_slowdown>
+			<clocks_throttle_reason_sw_thermal_slowdown>Active</clocks_throttle_reason_sw_thermal_slowdown>
+		</clocks_throttle_reasons>
+		<power_readings>
+			<power_state>P0</power_state>
+			<power_management>Supported</power_management>
+			<power_draw>249.31 W</power_draw>
+			<power_limit>250.00 W</power_limit>
+			<default_power_limit>250.00 W</default_power_limit>
+			<enforced_power_limit>250.00 W</enforced_power_limit>
+			<min_power_limit>100.00 W</min_power_limit>
+			<max_power_limit>250.00 W</max_power_limit>
+		</power_readings>
+		<clocks>
+			<graphics_clock>1230 MHz</graphics_clock>
+			<sm_clock>1230 MHz</sm_clock>
+			<mem_clock>877 MHz</mem_clock>
+		</clocks>
+		<max_clocks>
+			<graphics_clock>1380 MHz</graphics_clock>
+			<sm_clock>1380 MHz</sm_clock>
+			<mem_clock>877 MHz</mem_clock>
+		</max_clocks>
+	</gpu>
+</nvidia_smi_log>
+"#;
+
+    // 550 驱动，两张 RTX 4090：gpu_power_readings + clocks_event_reasons，消费级显卡没有 ECC 和序列号
+    const DRIVER_550: &str = r#"<?xml version="1.0" ?>
+<!DOCTYPE nvidia_smi_log SYSTEM "nvsmi_device_v12.dtd">
+<nvidia_smi_log>
+	<driver_version>550.54.14</driver_version>
+	<cuda_version>12.4</cuda_version>
+	<attached_gpus>2</attached_gpus>
+	<gpu id="00000000:01:00.0">
+		<product_name>NVIDIA GeForce RTX 4090</product_name>
+		<persistence_mode>Enabled</persistence_mode>
+		<serial>N/A</serial>
+		<uuid>GPU-1f2e3d4c-5b6a-4798-8a9b-0c1d2e3f4a5b</uuid>
+		<vbios_version>95.02.3C.40.A8</vbios_version>
+		<fan_speed>30 %</fan_speed>
+		<compute_mode>Exclusive_Process</compute_mode>
+		<utilization>
+			<gpu_util>0 %</gpu_util>
+			<memory_util>0 %</memory_util>
+			<encoder_util>0 %</encoder_util>
+			<decoder_util>0 %</decoder_util>
+		</utilization>
+		<ecc_mode>
+			<current_ecc>N/A</current_ecc>
+			<pending_ecc>N/A</pending_ecc>
+		</ecc_mode>
+		<retired_pages>
+			<multiple_single_bit_retirement>
+				<retired_count>N/A</retired_count>
+			</multiple_single_bit_retirement>
+			<double_bit_retirement>
+				<retired_count>N/A</retired_count>
+			</double_bit_retirement>
+			<pending_retirement>N/A</pending_retirement>
+		</retired_pages>
+		<clocks_event_reasons>
+			<clocks_event_reason_gpu_idle>Active</clocks_event_reason_gpu_idle>
+			<clocks_event_reason_applications_clocks_setting>Not Active</clocks_event_reason_applications_clocks_setting>
+			<clocks_event_reason_sw_power_cap>Not Active</clocks_event_reason_sw_power_cap>
+		</clocks_event_reasons>
+		<gpu_power_readings>
+			<power_state>P8</power_state>
+			<power_draw>21.37 W</power_draw>
+			<current_power_limit>450.00 W</current_power_limit>
+			<requested_power_limit>450.00 W</requested_power_limit>
+			<default_power_limit>450.00 W</default_power_limit>
+			<min_power_limit>150.00 W</min_power_limit>
+			<max_power_limit>600.00 W</max_power_limit>
+		</gpu_power_readings>
+		<module_power_readings>
+			<power_draw>N/A</power_draw>
+			<current_power_limit>N/A</current_power_limit>
+		</module_power_readings>
+		<clocks>
+			<sm_clock>210 MHz</sm_clock>
+			<mem_clock>405 MHz</mem_clock>
+		</clocks>
+		<max_clocks>
+			<sm_clock>3105 MHz</sm_clock>
+			<mem_clock>10501 MHz</mem_clock>
+		</max_clocks>
+	</gpu>
+	<gpu id="00000000:C1:00.0">
+		<product_name>NVIDIA GeForce RTX 4090</product_name>
+		<uuid>GPU-9a8b7c6d-5e4f-4321-8fed-cba987654321</uuid>
+		<utilization>
+			<gpu_util>100 %</gpu_util>
+		</utilization>
+		<gpu_power_readings>
+			<current_power_limit>400.00 W</current_power_limit>
+		</gpu_power_readings>
+	</gpu>
+</nvidia_smi_log>
+"#;
+
+    #[test]
+    fn parses_power_readings_and_throttle_reasons() {
+        let details = parse_nvidia_smi_xml(DRIVER_470).unwrap();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0], GpuDetails {
+            uuid: "GPU-8a5f1b0e-6c2d-4f3a-9e1b-7d0c2a4b6e81".to_string(),
+            gpu_bus_id: "00000000:3B:00.0".to_string(),
+            driver_version: Some("470.182.03".to_string()),
+            cuda_version: Some("11.4".to_string()),
+            vbios_version: Some("88.00.80.00.01".to_string()),
+            serial: Some("0323418012345".to_string()),
+            gpu_utilization_percent: Some(87.0),
+            memory_utilization_percent: Some(41.0),
+            encoder_utilization_percent: Some(0.0),
+            decoder_utilization_percent: Some(0.0),
+            sm_clock_mhz: Some(1230),
+            max_sm_clock_mhz: Some(1380),
+            memory_clock_mhz: Some(877),
+            max_memory_clock_mhz: Some(877),
+            fan_speed_percent: None,
+            power_limit_watts: Some(250.0),
+            default_power_limit_watts: Some(250.0),
+            min_power_limit_watts: Some(100.0),
+            max_power_limit_watts: Some(250.0),
+            persistence_mode: Some(false),
+            compute_mode: Some("Default".to_string()),
+            ecc_mode: Some(true),
+            retired_pages_single_bit: Some(2),
+            retired_pages_double_bit: Some(0),
+            retired_pages_pending: Some(false),
+            throttle_reasons: vec!["sw_power_cap".to_string(), "sw_thermal_slowdown".to_string()],
+        });
+    }
+
+    #[test]
+    fn parses_gpu_power_readings_and_event_reasons() {
+        let details = parse_nvidia_smi_xml(DRIVER_550).unwrap();
+        assert_eq!(details.len(), 2);
+
+        let gpu = &details[0];
+        assert_eq!(gpu.gpu_bus_id, "00000000:01:00.0");
+        assert_eq!(gpu.driver_version.as_deref(), Some("550.54.14"));
+        assert_eq!(gpu.cuda_version.as_deref(), Some("12.4"));
+        assert_eq!(gpu.serial, None);
+        assert_eq!(gpu.fan_speed_percent, Some(30.0));
+        assert_eq!(gpu.power_limit_watts, Some(450.0));
+        assert_eq!(gpu.min_power_limit_watts, Some(150.0));
+        assert_eq!(gpu.max_power_limit_watts, Some(600.0));
+        assert_eq!(gpu.sm_clock_mhz, Some(210));
+        assert_eq!(gpu.max_sm_clock_mhz, Some(3105));
+        assert_eq!(gpu.max_memory_clock_mhz, Some(10501));
+        assert_eq!(gpu.persistence_mode, Some(true));
+        assert_eq!(gpu.compute_mode.as_deref(), Some("Exclusive_Process"));
+        assert_eq!(gpu.ecc_mode, None);
+        assert_eq!(gpu.retired_pages_single_bit, None);
+        assert_eq!(gpu.retired_pages_pending, None);
+        assert_eq!(gpu.throttle_reasons, vec!["gpu_idle".to_string()]);
+
+        // 第二张卡字段不全，驱动版本仍然来自根元素
+        let gpu = &details[1];
+        assert_eq!(gpu.uuid, "GPU-9a8b7c6d-5e4f-4321-8fed-cba987654321");
+        assert_eq!(gpu.gpu_bus_id, "00000000:C1:00.0");
+        assert_eq!(gpu.driver_version.as_deref(), Some("550.54.14"));
+        assert_eq!(gpu.gpu_utilization_percent, Some(100.0));
+        assert_eq!(gpu.power_limit_watts, Some(400.0));
+        assert_eq!(gpu.default_power_limit_watts, None);
+        assert!(gpu.throttle_reasons.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_xml() {
+        assert!(parse_nvidia_smi_xml("NVIDIA-SMI has failed because it couldn't communicate with the NVIDIA driver.").is_err());
+    }
+}
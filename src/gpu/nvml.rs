@@ -0,0 +1,522 @@
+use std::error::Error;
+use std::ffi::{c_char, c_int, c_uint, c_ulong, c_void};
+use std::sync::OnceLock;
+
+use crate::GpuInfo;
+use crate::pci::{PciAddress, PciDevice};
+use crate::sysfs;
+use super::{GpuDetails, GpuProbe, legacy_memory, legacy_power, legacy_temperature};
+use super::mig::attach_mig_instances;
+use super::topology::{GpuAffinity, GpuTopology, LinkType};
+
+#[cfg(target_os = "windows")]
+const NVML_LIBRARY: &str = "nvml.dll";
+#[cfg(not(target_os = "windows"))]
+const NVML_LIBRARY: &str = "libnvidia-ml.so.1";
+
+const NVML_SUCCESS: c_int = 0;
+const NVML_ERROR_INSUFFICIENT_SIZE: c_int = 7;
+
+const NVML_TEMPERATURE_GPU: c_uint = 0;
+const NVML_CLOCK_SM: c_uint = 1;
+const NVML_CLOCK_MEM: c_uint = 2;
+const NVML_PAGE_RETIREMENT_CAUSE_SINGLE_BIT: c_uint = 0;
+const NVML_PAGE_RETIREMENT_CAUSE_DOUBLE_BIT: c_uint = 1;
+const NVML_NVLINK_MAX_LINKS: c_uint = 18;
+/// nvmlDeviceGetCpuAffinity 的缓冲区大小，按 c_ulong 计，足够 1024 个 CPU
+const CPU_SET_SIZE: usize = 1024 / (std::mem::size_of::<c_ulong>() * 8);
+
+/// nvmlClocksThrottleReasons 的位，名称与 nvidia-smi -q -x 保持一致
+const THROTTLE_REASONS: [(u64, &str); 9] = [
+    (0x1, "gpu_idle"),
+    (0x2, "applications_clocks_setting"),
+    (0x4, "sw_power_cap"),
+    (0x8, "hw_slowdown"),
+    (0x10, "sync_boost"),
+    (0x20, "sw_thermal_slowdown"),
+    (0x40, "hw_thermal_slowdown"),
+    (0x80, "hw_power_brake_slowdown"),
+    (0x100, "display_clock_setting"),
+];
+
+type Device = *mut c_void;
+
+#[repr(C)]
+struct NvmlMemory {
+    total: u64,
+    free: u64,
+    used: u64,
+}
+
+#[repr(C)]
+struct NvmlUtilization {
+    gpu: c_uint,
+    memory: c_uint,
+}
+
+#[repr(C)]
+struct NvmlPciInfo {
+    bus_id_legacy: [c_char; 16],
+    domain: c_uint,
+    bus: c_uint,
+    device: c_uint,
+    pci_device_id: c_uint,
+    pci_sub_system_id: c_uint,
+    bus_id: [c_char; 32],
+}
+
+impl NvmlPciInfo {
+    fn empty() -> Self {
+        NvmlPciInfo {
+            bus_id_legacy: [0; 16],
+            domain: 0,
+            bus: 0,
+            device: 0,
+            pci_device_id: 0,
+            pci_sub_system_id: 0,
+            bus_id: [0; 32],
+        }
+    }
+}
+
+/// 运行时加载的 NVML，不在编译期链接，没有安装驱动的机器也能编译和运行
+pub(crate) struct Nvml {
+    lib: libloading::Library,
+}
+
+impl Nvml {
+    /// 加载并初始化 NVML，只尝试一次，失败时返回 None
+    pub(crate) fn get() -> Option<&'static Nvml> {
+        static NVML: OnceLock<Option<Nvml>> = OnceLock::new();
+        NVML.get_or_init(|| Nvml::load(NVML_LIBRARY)).as_ref()
+    }
+
+    /// 加载指定的库并调用 nvmlInit_v2，库不存在或初始化失败时返回 None
+    fn load(library: &str) -> Option<Nvml> {
+        // 加载的是 NVIDIA 驱动自带的库，初始化函数没有额外的前置条件
+        let lib = unsafe { libloading::Library::new(library) }.ok()?;
+        let nvml = Nvml { lib };
+        let init = unsafe { nvml.lib.get::<unsafe extern "C" fn() -> c_int>(b"nvmlInit_v2\0") }.ok()?;
+        if unsafe { init() } != NVML_SUCCESS {
+            return None;
+        }
+        Some(nvml)
+    }
+
+    /// 显卡当前是否开启了 MIG，不支持 MIG 的显卡调用失败，视为未开启
+    fn mig_enabled(&self, index: u32) -> bool {
+        let device = match self.device(index) {
+            Some(device) => device,
+            None => return false,
+        };
+        matches!(self.uint_pair("nvmlDeviceGetMigMode", device), Some((1, _)))
+    }
+
+    fn symbol<T>(&self, name: &str) -> Option<libloading::Symbol<'_, T>> {
+        let name = format!("{}\0", name);
+        unsafe { self.lib.get::<T>(name.as_bytes()) }.ok()
+    }
+
+    fn device_count(&self) -> Option<u32> {
+        let f = self.symbol::<unsafe extern "C" fn(*mut c_uint) -> c_int>("nvmlDeviceGetCount_v2")?;
+        let mut count: c_uint = 0;
+        (unsafe { f(&mut count) } == NVML_SUCCESS).then_some(count)
+    }
+
+    fn device(&self, index: u32) -> Option<Device> {
+        let f = self.symbol::<unsafe extern "C" fn(c_uint, *mut Device) -> c_int>("nvmlDeviceGetHandleByIndex_v2")?;
+        let mut device: Device = std::ptr::null_mut();
+        (unsafe { f(index, &mut device) } == NVML_SUCCESS).then_some(device)
+    }
+
+    fn system_string(&self, name: &str) -> Option<String> {
+        let f = self.symbol::<unsafe extern "C" fn(*mut c_char, c_uint) -> c_int>(name)?;
+        let mut buffer = [0 as c_char; 96];
+        if unsafe { f(buffer.as_mut_ptr(), buffer.len() as c_uint) } != NVML_SUCCESS {
+            return None;
+        }
+        Some(c_string(&buffer))
+    }
+
+    fn cuda_version(&self) -> Option<String> {
+        let f = self.symbol::<unsafe extern "C" fn(*mut c_int) -> c_int>("nvmlSystemGetCudaDriverVersion_v2")?;
+        let mut version: c_int = 0;
+        if unsafe { f(&mut version) } != NVML_SUCCESS {
+            return None;
+        }
+        // 12040 -> 12.4
+        Some(format!("{}.{}", version / 1000, version % 1000 / 10))
+    }
+
+    fn string(&self, name: &str, device: Device) -> Option<String> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int>(name)?;
+        let mut buffer = [0 as c_char; 96];
+        if unsafe { f(device, buffer.as_mut_ptr(), buffer.len() as c_uint) } != NVML_SUCCESS {
+            return None;
+        }
+        Some(c_string(&buffer))
+    }
+
+    fn uint(&self, name: &str, device: Device) -> Option<u32> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut c_uint) -> c_int>(name)?;
+        let mut value: c_uint = 0;
+        (unsafe { f(device, &mut value) } == NVML_SUCCESS).then_some(value)
+    }
+
+    fn uint_with(&self, name: &str, device: Device, arg: c_uint) -> Option<u32> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int>(name)?;
+        let mut value: c_uint = 0;
+        (unsafe { f(device, arg, &mut value) } == NVML_SUCCESS).then_some(value)
+    }
+
+    fn uint_pair(&self, name: &str, device: Device) -> Option<(u32, u32)> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut c_uint, *mut c_uint) -> c_int>(name)?;
+        let (mut first, mut second): (c_uint, c_uint) = (0, 0);
+        (unsafe { f(device, &mut first, &mut second) } == NVML_SUCCESS).then_some((first, second))
+    }
+
+    fn memory(&self, device: Device) -> Option<NvmlMemory> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut NvmlMemory) -> c_int>("nvmlDeviceGetMemoryInfo")?;
+        let mut memory = NvmlMemory { total: 0, free: 0, used: 0 };
+        (unsafe { f(device, &mut memory) } == NVML_SUCCESS).then_some(memory)
+    }
+
+    fn utilization(&self, device: Device) -> Option<NvmlUtilization> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut NvmlUtilization) -> c_int>("nvmlDeviceGetUtilizationRates")?;
+        let mut utilization = NvmlUtilization { gpu: 0, memory: 0 };
+        (unsafe { f(device, &mut utilization) } == NVML_SUCCESS).then_some(utilization)
+    }
+
+    fn bus_id(&self, device: Device) -> Option<String> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut NvmlPciInfo) -> c_int>("nvmlDeviceGetPciInfo_v3")?;
+        let mut pci = NvmlPciInfo::empty();
+        if unsafe { f(device, &mut pci) } != NVML_SUCCESS {
+            return None;
+        }
+        Some(c_string(&pci.bus_id))
+    }
+
+    /// NVLink 对端的 PCI 地址，对端可能是另一张显卡，也可能是 NVSwitch
+    fn nvlink_remote(&self, device: Device, link: c_uint) -> Option<PciAddress> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, c_uint, *mut NvmlPciInfo) -> c_int>("nvmlDeviceGetNvLinkRemotePciInfo_v2")?;
+        let mut pci = NvmlPciInfo::empty();
+        if unsafe { f(device, link, &mut pci) } != NVML_SUCCESS {
+            return None;
+        }
+        PciAddress::parse(&c_string(&pci.bus_id))
+    }
+
+    /// 两张显卡在 PCIe 树上的最近公共节点，返回 nvmlGpuTopologyLevel_t
+    fn common_ancestor(&self, first: Device, second: Device) -> Option<u32> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, Device, *mut c_uint) -> c_int>("nvmlDeviceGetTopologyCommonAncestor")?;
+        let mut level: c_uint = 0;
+        (unsafe { f(first, second, &mut level) } == NVML_SUCCESS).then_some(level)
+    }
+
+    fn cpu_affinity(&self, device: Device) -> Option<Vec<u32>> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, c_uint, *mut c_ulong) -> c_int>("nvmlDeviceGetCpuAffinity")?;
+        let mut cpu_set = [0 as c_ulong; CPU_SET_SIZE];
+        if unsafe { f(device, CPU_SET_SIZE as c_uint, cpu_set.as_mut_ptr()) } != NVML_SUCCESS {
+            return None;
+        }
+
+        let bits = std::mem::size_of::<c_ulong>() * 8;
+        Some((0..CPU_SET_SIZE * bits)
+            .filter(|cpu| cpu_set[cpu / bits] & (1 << (cpu % bits)) != 0)
+            .map(|cpu| cpu as u32)
+            .collect())
+    }
+
+    /// 与 nvidia-smi topo -m 相同的拓扑矩阵
+    pub(crate) fn topology(&self) -> Option<GpuTopology> {
+        let count = self.device_count()? as usize;
+        let devices: Vec<Device> = (0..count as u32).map(|index| self.device(index)).collect::<Option<_>>()?;
+        let addresses: Vec<Option<PciAddress>> = devices
+            .iter()
+            .map(|device| self.bus_id(*device).and_then(|id| PciAddress::parse(&id)))
+            .collect();
+
+        // nvlinks[i][j] 是 i 到 j 直连的 NVLink 数，switch_links[i] 是 i 连到 NVSwitch 的 NVLink 数
+        let mut nvlinks = vec![vec![0u32; count]; count];
+        let mut switch_links = vec![0u32; count];
+        for (i, device) in devices.iter().enumerate() {
+            for link in 0..NVML_NVLINK_MAX_LINKS {
+                if self.uint_with("nvmlDeviceGetNvLinkState", *device, link) != Some(1) {
+                    continue;
+                }
+                let remote = match self.nvlink_remote(*device, link) {
+                    Some(remote) => remote,
+                    None => continue,
+                };
+                match addresses.iter().position(|address| *address == Some(remote)) {
+                    Some(j) => nvlinks[i][j] += 1,
+                    None => switch_links[i] += 1,
+                }
+            }
+        }
+
+        let links = (0..count)
+            .map(|i| {
+                (0..count)
+                    .map(|j| {
+                        if i == j {
+                            return LinkType::SameDevice;
+                        }
+                        if nvlinks[i][j] > 0 {
+                            return LinkType::NvLink(nvlinks[i][j]);
+                        }
+                        if switch_links[i] > 0 && switch_links[j] > 0 {
+                            return LinkType::NvLink(switch_links[i].min(switch_links[j]));
+                        }
+                        match self.common_ancestor(devices[i], devices[j]) {
+                            Some(0) | Some(10) => LinkType::Pix,
+                            Some(20) => LinkType::Pxb,
+                            Some(30) => LinkType::Phb,
+                            Some(40) => LinkType::Node,
+                            Some(50) => LinkType::Sys,
+                            Some(level) => LinkType::Other(level.to_string()),
+                            None => LinkType::Other("N/A".to_string()),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let gpus: Vec<String> = (0..count).map(|index| format!("GPU{}", index)).collect();
+        let affinity = devices
+            .iter()
+            .enumerate()
+            .map(|(index, device)| GpuAffinity {
+                gpu: gpus[index].clone(),
+                cpus: self.cpu_affinity(*device).unwrap_or_default(),
+                numa_node: addresses[index].and_then(|address| {
+                    sysfs::read_string(format!("/sys/bus/pci/devices/{}/numa_node", address))?.parse::<u32>().ok()
+                }),
+            })
+            .collect();
+
+        Some(GpuTopology { gpus, links, affinity })
+    }
+
+    fn throttle_reasons(&self, device: Device) -> Option<u64> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, *mut u64) -> c_int>("nvmlDeviceGetCurrentClocksThrottleReasons")?;
+        let mut reasons: u64 = 0;
+        (unsafe { f(device, &mut reasons) } == NVML_SUCCESS).then_some(reasons)
+    }
+
+    fn retired_pages(&self, device: Device, cause: c_uint) -> Option<u64> {
+        let f = self.symbol::<unsafe extern "C" fn(Device, c_uint, *mut c_uint, *mut u64) -> c_int>("nvmlDeviceGetRetiredPages")?;
+        // 传入 0 长度只查询数量
+        let mut count: c_uint = 0;
+        match unsafe { f(device, cause, &mut count, std::ptr::null_mut()) } {
+            NVML_SUCCESS | NVML_ERROR_INSUFFICIENT_SIZE => Some(count as u64),
+            _ => None,
+        }
+    }
+
+    /// 后台采样只读利用率、显存、温度、功耗
+    fn gpu_sample(&self, index: u32) -> Option<GpuInfo> {
+        let device = self.device(index)?;
+
+        let memory = self.memory(device);
+        let memory_used_bytes = memory.as_ref().map(|memory| memory.used);
+        let memory_total_bytes = memory.as_ref().map(|memory| memory.total);
+        let temperature_celsius = self.uint_with("nvmlDeviceGetTemperature", device, NVML_TEMPERATURE_GPU)
+            .map(|value| value as f32);
+        let power_draw_watts = self.uint("nvmlDeviceGetPowerUsage", device).map(|value| value as f32 / 1000.0);
+
+        Some(GpuInfo {
+            index: self.uint("nvmlDeviceGetIndex", device).unwrap_or(index).to_string(),
+            uuid: self.string("nvmlDeviceGetUUID", device).unwrap_or_default(),
+            gpu_bus_id: self.bus_id(device).unwrap_or_default(),
+            memory_used: legacy_memory(memory_used_bytes),
+            memory_total: legacy_memory(memory_total_bytes),
+            temperature: legacy_temperature(temperature_celsius),
+            power_draw: legacy_power(power_draw_watts),
+            memory_used_bytes,
+            memory_total_bytes,
+            temperature_celsius,
+            power_draw_watts,
+            utilization_percent: self.utilization(device).map(|utilization| utilization.gpu as f32),
+            vendor: "nvidia".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn gpu_info(&self, index: u32, driver_version: &Option<String>, cuda_version: &Option<String>) -> Option<GpuInfo> {
+        let device = self.device(index)?;
+
+        let memory = self.memory(device);
+        let memory_used_bytes = memory.as_ref().map(|memory| memory.used);
+        let memory_total_bytes = memory.as_ref().map(|memory| memory.total);
+        let temperature_celsius = self.uint_with("nvmlDeviceGetTemperature", device, NVML_TEMPERATURE_GPU)
+            .map(|value| value as f32);
+        // 功耗相关的接口单位都是毫瓦
+        let milliwatts = |value: u32| value as f32 / 1000.0;
+        let power_draw_watts = self.uint("nvmlDeviceGetPowerUsage", device).map(milliwatts);
+        let utilization = self.utilization(device);
+        let power_limits = self.uint_pair("nvmlDeviceGetPowerManagementLimitConstraints", device);
+        let uuid = self.string("nvmlDeviceGetUUID", device).unwrap_or_default();
+        let gpu_bus_id = self.bus_id(device).unwrap_or_default();
+
+        let details = GpuDetails {
+            uuid: uuid.clone(),
+            gpu_bus_id: gpu_bus_id.clone(),
+            driver_version: driver_version.clone(),
+            cuda_version: cuda_version.clone(),
+            vbios_version: self.string("nvmlDeviceGetVbiosVersion", device),
+            serial: self.string("nvmlDeviceGetSerial", device),
+
+            gpu_utilization_percent: utilization.as_ref().map(|utilization| utilization.gpu as f32),
+            memory_utilization_percent: utilization.as_ref().map(|utilization| utilization.memory as f32),
+            encoder_utilization_percent: self.uint_pair("nvmlDeviceGetEncoderUtilization", device)
+                .map(|(value, _)| value as f32),
+            decoder_utilization_percent: self.uint_pair("nvmlDeviceGetDecoderUtilization", device)
+                .map(|(value, _)| value as f32),
+
+            sm_clock_mhz: self.uint_with("nvmlDeviceGetClockInfo", device, NVML_CLOCK_SM),
+            max_sm_clock_mhz: self.uint_with("nvmlDeviceGetMaxClockInfo", device, NVML_CLOCK_SM),
+            memory_clock_mhz: self.uint_with("nvmlDeviceGetClockInfo", device, NVML_CLOCK_MEM),
+            max_memory_clock_mhz: self.uint_with("nvmlDeviceGetMaxClockInfo", device, NVML_CLOCK_MEM),
+            fan_speed_percent: self.uint("nvmlDeviceGetFanSpeed", device).map(|value| value as f32),
+
+            power_limit_watts: self.uint("nvmlDeviceGetPowerManagementLimit", device).map(milliwatts),
+            default_power_limit_watts: self.uint("nvmlDeviceGetPowerManagementDefaultLimit", device).map(milliwatts),
+            min_power_limit_watts: power_limits.map(|(min, _)| milliwatts(min)),
+            max_power_limit_watts: power_limits.map(|(_, max)| milliwatts(max)),
+
+            persistence_mode: self.uint("nvmlDeviceGetPersistenceMode", device).map(|value| value == 1),
+            compute_mode: self.uint("nvmlDeviceGetComputeMode", device).and_then(|value| match value {
+                0 => Some("Default".to_string()),
+                2 => Some("Prohibited".to_string()),
+                3 => Some("Exclusive_Process".to_string()),
+                _ => None,
+            }),
+            ecc_mode: self.uint_pair("nvmlDeviceGetEccMode", device).map(|(current, _)| current == 1),
+
+            retired_pages_single_bit: self.retired_pages(device, NVML_PAGE_RETIREMENT_CAUSE_SINGLE_BIT),
+            retired_pages_double_bit: self.retired_pages(device, NVML_PAGE_RETIREMENT_CAUSE_DOUBLE_BIT),
+            retired_pages_pending: self.uint("nvmlDeviceGetRetiredPagesPendingStatus", device).map(|value| value == 1),
+            throttle_reasons: self.throttle_reasons(device).map(throttle_reason_names).unwrap_or_default(),
+        };
+
+        Some(GpuInfo {
+            index: self.uint("nvmlDeviceGetIndex", device).unwrap_or(index).to_string(),
+            name: self.string("nvmlDeviceGetName", device).unwrap_or_default(),
+            uuid,
+            gpu_bus_id,
+            memory_used: legacy_memory(memory_used_bytes),
+            memory_total: legacy_memory(memory_total_bytes),
+            temperature: legacy_temperature(temperature_celsius),
+            power_draw: legacy_power(power_draw_watts),
+            memory_used_bytes,
+            memory_total_bytes,
+            temperature_celsius,
+            power_draw_watts,
+            utilization_percent: details.gpu_utilization_percent,
+            clock_mhz: details.sm_clock_mhz,
+            max_clock_mhz: details.max_sm_clock_mhz,
+            details: Some(details),
+            vendor: "nvidia".to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// NVML 返回的以 \0 结尾的字符串
+fn c_string(buffer: &[c_char]) -> String {
+    let bytes: Vec<u8> = buffer.iter().take_while(|c| **c != 0).map(|c| *c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// 降频原因的位掩码转为名称，未知的位忽略
+fn throttle_reason_names(reasons: u64) -> Vec<String> {
+    THROTTLE_REASONS
+        .iter()
+        .filter(|(bit, _)| reasons & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// 通过 NVML 获取 N 卡信息，加载不到 libnvidia-ml 时跳过，由 nvidia-smi 后端接手
+pub struct NvmlProbe;
+
+impl GpuProbe for NvmlProbe {
+    fn name(&self) -> &'static str {
+        "nvml"
+    }
+
+    fn vendor(&self) -> &'static str {
+        "nvidia"
+    }
+
+    fn detect(&self, devices: &[PciDevice]) -> bool {
+        devices.iter().any(|device| device.vendor_id == 0x10de && device.is_display()) && Nvml::get().is_some()
+    }
+
+    fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        let nvml = Nvml::get().ok_or("NVML 不可用")?;
+        let count = nvml.device_count().ok_or("nvmlDeviceGetCount 调用失败")?;
+
+        let driver_version = nvml.system_string("nvmlSystemGetDriverVersion");
+        let cuda_version = nvml.cuda_version();
+
+        let mut gpu_info: Vec<GpuInfo> = (0..count)
+            .filter_map(|index| nvml.gpu_info(index, &driver_version, &cuda_version))
+            .collect();
+        // MIG 实例还要通过 nvidia-smi 查询，只在有显卡开启了 MIG 时执行
+        if (0..count).any(|index| nvml.mig_enabled(index)) {
+            attach_mig_instances(&mut gpu_info);
+        }
+        Ok(gpu_info)
+    }
+
+    fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+        let nvml = Nvml::get().ok_or("NVML 不可用")?;
+        let count = nvml.device_count().ok_or("nvmlDeviceGetCount 调用失败")?;
+        Ok((0..count).filter_map(|index| nvml.gpu_sample(index)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_c_strings() {
+        let buffer: Vec<c_char> = b"535.154.05\0garbage".iter().map(|c| *c as c_char).collect();
+        assert_eq!(c_string(&buffer), "535.154.05");
+
+        // 缓冲区写满、没有 \0 时取整个缓冲区
+        let buffer: Vec<c_char> = b"NVIDIA A100".iter().map(|c| *c as c_char).collect();
+        assert_eq!(c_string(&buffer), "NVIDIA A100");
+
+        assert_eq!(c_string(&[0; 8]), "");
+        assert_eq!(c_string(&[]), "");
+    }
+
+    #[test]
+    fn maps_throttle_reasons() {
+        assert!(throttle_reason_names(0).is_empty());
+        assert_eq!(throttle_reason_names(0x1), vec!["gpu_idle"]);
+        assert_eq!(
+            throttle_reason_names(0x4 | 0x40 | 0x100),
+            vec!["sw_power_cap", "hw_thermal_slowdown", "display_clock_setting"],
+        );
+        // 新驱动增加的位不认识，忽略
+        assert_eq!(throttle_reason_names(0x8 | 0x1000), vec!["hw_slowdown"]);
+    }
+
+    #[test]
+    fn missing_library_is_an_error() {
+        assert!(Nvml::load("libnvidia-ml-missing.so.1").is_none());
+
+        // 没有安装驱动的机器上三个入口都不能 panic
+        if Nvml::get().is_none() {
+            let gpu = PciDevice { vendor_id: 0x10de, class: 0x030200, ..Default::default() };
+            assert!(!NvmlProbe.detect(&[gpu]));
+            assert!(NvmlProbe.enumerate().is_err());
+            assert!(NvmlProbe.sample().is_err());
+        }
+    }
+}
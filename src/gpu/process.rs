@@ -0,0 +1,265 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::error::Error;
+use std::path::Path;
+
+use super::{GpuRegistry, parse_memory_bytes};
+
+/// 占用显卡的计算进程
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GpuProcess {
+    pub pid: u32,
+    /// 与 GpuInfo.uuid 对应
+    pub gpu_uuid: String,
+    /// 占用的显存，单位字节
+    pub used_memory_bytes: Option<u64>,
+    /// 进程所在容器的完整 id，不在容器里时为 None
+    pub container_id: Option<String>,
+    /// containers 列表中对应容器的名称
+    pub container_name: Option<String>,
+}
+
+/// 查询所有显卡上的计算进程，并通过 /proc/<pid>/cgroup 找到所在的容器
+/// 没有 N 卡、没有安装 nvidia-smi 或者驱动没有加载的机器返回空列表
+pub fn gpu_processes(containers: &Value) -> Result<Vec<GpuProcess>, Box<dyn Error>> {
+    if !GpuRegistry::shared().has_nvidia() {
+        return Ok(vec![]);
+    }
+
+    let output = match std::process::Command::new("nvidia-smi")
+        .arg("--query-compute-apps=pid,gpu_uuid,used_memory")
+        .arg("--format=csv,noheader")
+        .output() {
+            Ok(output) => output,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stdout);
+        if driver_missing(&message) {
+            info!("NVIDIA 驱动未加载，跳过显卡进程查询");
+            return Ok(vec![]);
+        }
+        return Err(format!("nvidia-smi --query-compute-apps 执行失败: {}", message.trim()).into());
+    }
+
+    let mut processes = parse_compute_apps(&String::from_utf8_lossy(&output.stdout));
+    resolve_containers(&mut processes, Path::new("/proc"), containers);
+    Ok(processes)
+}
+
+/// nvidia-smi 找不到驱动或设备时的输出，如
+/// "NVIDIA-SMI has failed because it couldn't communicate with the NVIDIA driver."
+fn driver_missing(output: &str) -> bool {
+    output.contains("couldn't communicate with the NVIDIA driver") || output.contains("No devices were found")
+}
+
+/// 解析 --query-compute-apps=pid,gpu_uuid,used_memory --format=csv,noheader 的输出
+pub fn parse_compute_apps(output: &str) -> Vec<GpuProcess> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            if fields.len() < 3 {
+                return None;
+            }
+            Some(GpuProcess {
+                pid: fields[0].parse().ok()?,
+                gpu_uuid: fields[1].to_string(),
+                used_memory_bytes: parse_memory_bytes(fields[2]),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// 填充进程的容器 id 和名称，proc_root 一般是 /proc，测试时可以指向伪造的目录
+pub fn resolve_containers(processes: &mut [GpuProcess], proc_root: &Path, containers: &Value) {
+    if processes.is_empty() {
+        return;
+    }
+
+    let containers = match ContainerPs::deserialize(containers) {
+        Ok(container_ps) => container_ps.data,
+        Err(err) => {
+            info!("解析 container_ps 输出失败: {}", err);
+            vec![]
+        }
+    };
+
+    for process in processes.iter_mut() {
+        let cgroup = match std::fs::read_to_string(proc_root.join(process.pid.to_string()).join("cgroup")) {
+            Ok(cgroup) => cgroup,
+            Err(_) => continue,
+        };
+
+        process.container_id = container_id_from_cgroup(&cgroup);
+        if let Some(container_id) = &process.container_id {
+            process.container_name = container_name(&containers, container_id);
+        }
+    }
+}
+
+/// 从 /proc/<pid>/cgroup 中取出容器 id，兼容以下写法：
+/// - cgroup v1: `12:memory:/docker/<id>`
+/// - cgroup v2 + systemd: `0::/system.slice/docker-<id>.scope`
+/// - containerd / kubernetes: `0::/kubepods/besteffort/pod<uid>/cri-containerd-<id>.scope`
+pub fn container_id_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup.lines().rev().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        path.rsplit('/').find_map(|segment| {
+            let segment = segment.trim_end_matches(".scope");
+            let id = segment.rsplit('-').next().unwrap_or(segment);
+            if id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+                Some(id.to_string())
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// wei-docker container_ps 的输出，如 `{"code": 200, "data": [...]}`
+#[derive(Deserialize)]
+struct ContainerPs {
+    #[serde(default)]
+    data: Vec<DockerContainer>,
+}
+
+/// data 中的一项，即 `docker ps --format '{{json .}}'` 的一行，只用到 ID 和 Names
+#[derive(Deserialize)]
+struct DockerContainer {
+    /// 12 位短 id
+    #[serde(rename = "ID")]
+    id: String,
+    /// 有多个名称时以逗号分隔
+    #[serde(rename = "Names")]
+    names: String,
+}
+
+/// 按 id 前缀查找容器名称，docker ps 给出的是 12 位短 id，cgroup 中是 64 位完整 id
+fn container_name(containers: &[DockerContainer], container_id: &str) -> Option<String> {
+    containers
+        .iter()
+        .find(|container| !container.id.is_empty() && container_id.starts_with(&container.id))
+        .and_then(|container| container.names.split(',').next())
+        .map(|name| name.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DOCKER_ID: &str = "4f1c2b8e9d7a6c5b3e2f1a0d9c8b7a6f5e4d3c2b1a0f9e8d7c6b5a4f3e2d1c0b";
+    const CRI_ID: &str = "a3b2c1d0e9f8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2";
+
+    // nvidia-smi --query-compute-apps=pid,gpu_uuid,used_memory --format=csv,noheader
+    const COMPUTE_APPS: &str = "\
+2871, GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11, 20480 MiB
+3120, GPU-0b7d3f52-1c8e-4a67-9d21-7e5c3a8b6f90, 1536 MiB
+4410, GPU-0b7d3f52-1c8e-4a67-9d21-7e5c3a8b6f90, [N/A]
+";
+
+    // wei-docker container_ps
+    fn container_ps() -> Value {
+        json!({
+            "code": 200,
+            "data": [
+                {
+                    "Command": "\"python train.py\"",
+                    "CreatedAt": "2024-03-04 10:12:45 +0800 CST",
+                    "ID": "4f1c2b8e9d7a",
+                    "Image": "pytorch/pytorch:2.2.1-cuda12.1-cudnn8-runtime",
+                    "Labels": "",
+                    "LocalVolumes": "0",
+                    "Mounts": "/data/model",
+                    "Names": "gpu-rent-7f3a",
+                    "Networks": "bridge",
+                    "Ports": "0.0.0.0:8888->8888/tcp",
+                    "RunningFor": "2 hours ago",
+                    "Size": "0B",
+                    "State": "running",
+                    "Status": "Up 2 hours"
+                },
+                {
+                    "Command": "\"/docker-entrypoint.…\"",
+                    "ID": "9e8d7c6b5a4f",
+                    "Image": "nginx:latest",
+                    "Names": "web,web-alias",
+                    "State": "running",
+                    "Status": "Up 3 days"
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn container_id_from_cgroup_formats() {
+        let v1 = format!("12:memory:/docker/{id}\n11:cpuset:/docker/{id}\n1:name=systemd:/docker/{id}\n", id = DOCKER_ID);
+        assert_eq!(container_id_from_cgroup(&v1).as_deref(), Some(DOCKER_ID));
+
+        let v2 = format!("0::/system.slice/docker-{}.scope\n", DOCKER_ID);
+        assert_eq!(container_id_from_cgroup(&v2).as_deref(), Some(DOCKER_ID));
+
+        let cri = format!(
+            "0::/kubepods.slice/kubepods-besteffort.slice/kubepods-besteffort-pod1b2c3d4e_5f6a_7b8c_9d0e_1f2a3b4c5d6e.slice/cri-containerd-{}.scope\n",
+            CRI_ID
+        );
+        assert_eq!(container_id_from_cgroup(&cri).as_deref(), Some(CRI_ID));
+
+        // 宿主机上的进程
+        assert_eq!(container_id_from_cgroup("0::/user.slice/user-1000.slice/session-3.scope\n"), None);
+        assert_eq!(container_id_from_cgroup("12:memory:/\n"), None);
+    }
+
+    #[test]
+    fn parses_compute_apps() {
+        let processes = parse_compute_apps(COMPUTE_APPS);
+        assert_eq!(processes.len(), 3);
+        assert_eq!(processes[0].pid, 2871);
+        assert_eq!(processes[0].gpu_uuid, "GPU-5e2a7c1d-93b1-4f0a-8c1e-2b6f0d9a4e11");
+        assert_eq!(processes[0].used_memory_bytes, Some(20480 << 20));
+        assert_eq!(processes[2].used_memory_bytes, None);
+    }
+
+    #[test]
+    fn resolves_containers_from_fake_procfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let write_cgroup = |pid: u32, cgroup: String| {
+            std::fs::create_dir_all(dir.path().join(pid.to_string())).unwrap();
+            std::fs::write(dir.path().join(pid.to_string()).join("cgroup"), cgroup).unwrap();
+        };
+        write_cgroup(2871, format!("0::/system.slice/docker-{}.scope\n", DOCKER_ID));
+        write_cgroup(3120, format!("0::/kubepods/besteffort/pod1234/cri-containerd-{}.scope\n", CRI_ID));
+        // 4410 已经退出，没有 /proc/4410
+
+        let mut processes = parse_compute_apps(COMPUTE_APPS);
+        resolve_containers(&mut processes, dir.path(), &container_ps());
+
+        assert_eq!(processes[0].container_id.as_deref(), Some(DOCKER_ID));
+        assert_eq!(processes[0].container_name.as_deref(), Some("gpu-rent-7f3a"));
+        // 不是 docker 启动的容器，找不到名称
+        assert_eq!(processes[1].container_id.as_deref(), Some(CRI_ID));
+        assert_eq!(processes[1].container_name, None);
+        assert_eq!(processes[2].container_id, None);
+    }
+
+    #[test]
+    fn container_name_uses_first_name() {
+        let containers = ContainerPs::deserialize(&container_ps()).unwrap().data;
+        let id = "9e8d7c6b5a4f00112233445566778899aabbccddeeff00112233445566778899";
+        assert_eq!(container_name(&containers, id).as_deref(), Some("web"));
+        assert_eq!(container_name(&containers, DOCKER_ID).as_deref(), Some("gpu-rent-7f3a"));
+        assert_eq!(container_name(&[], DOCKER_ID), None);
+    }
+
+    #[test]
+    fn missing_driver_is_not_an_error() {
+        assert!(driver_missing("NVIDIA-SMI has failed because it couldn't communicate with the NVIDIA driver. \
+Make sure that the latest NVIDIA driver is installed and running.\n"));
+        assert!(driver_missing("No devices were found\n"));
+        assert!(!driver_missing("Failed to initialize NVML: Driver/library version mismatch\n"));
+    }
+}
@@ -0,0 +1,382 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Serialize, Deserialize};
+use tokio::time::{Duration, Instant, MissedTickBehavior};
+
+use crate::GpuInfo;
+use super::{GpuRegistry, known_address};
+
+static SAMPLER: OnceLock<GpuSampler> = OnceLock::new();
+
+/// 一组采样值的统计
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SampleStats {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+/// 一张显卡在最近一个上报窗口内的统计，单位与 GpuInfo 中同名字段一致
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct GpuStats {
+    pub index: String,
+    pub uuid: String,
+    /// 与 GpuInfo 中同名字段一致，没有 uuid 的后端用厂商和总线地址对应到显卡
+    pub vendor: String,
+    pub gpu_bus_id: String,
+    /// 窗口内的采样次数
+    pub samples: usize,
+    pub window_seconds: u64,
+    pub utilization_percent: Option<SampleStats>,
+    pub memory_used_bytes: Option<SampleStats>,
+    pub temperature_celsius: Option<SampleStats>,
+    pub power_draw_watts: Option<SampleStats>,
+}
+
+/// 一次采样中一张显卡的数据
+struct GpuReading {
+    /// 跨采样识别同一张显卡
+    key: String,
+    index: String,
+    uuid: String,
+    vendor: String,
+    gpu_bus_id: String,
+    utilization_percent: Option<f64>,
+    memory_used_bytes: Option<f64>,
+    temperature_celsius: Option<f64>,
+    power_draw_watts: Option<f64>,
+}
+
+impl GpuReading {
+    fn new(gpu: &GpuInfo) -> Self {
+        GpuReading {
+            key: reading_key(gpu),
+            index: gpu.index.clone(),
+            uuid: gpu.uuid.clone(),
+            vendor: gpu.vendor.clone(),
+            gpu_bus_id: gpu.gpu_bus_id.clone(),
+            utilization_percent: gpu.utilization_percent.map(f64::from),
+            memory_used_bytes: gpu.memory_used_bytes.map(|bytes| bytes as f64),
+            temperature_celsius: gpu.temperature_celsius.map(f64::from),
+            power_draw_watts: gpu.power_draw_watts.map(f64::from),
+        }
+    }
+}
+
+/// 按厂商和 PCI 地址识别显卡，地址未知时退回到序号；
+/// 不用 uuid，采样时部分后端不填，各厂商的序号都从 0 开始，只用序号会把不同的卡合并
+fn reading_key(gpu: &GpuInfo) -> String {
+    match known_address(gpu) {
+        Some(address) => format!("{}/{}", gpu.vendor, address),
+        None => format!("{}/#{}", gpu.vendor, gpu.index),
+    }
+}
+
+/// 一次采样的读数，310P 等一卡多芯片的设备共用一个地址，再按序号区分
+fn readings(gpu_info: &[GpuInfo]) -> Vec<GpuReading> {
+    let mut gpus: Vec<GpuReading> = gpu_info.iter().map(GpuReading::new).collect();
+    let keys: Vec<String> = gpus.iter().map(|gpu| gpu.key.clone()).collect();
+    for gpu in gpus.iter_mut() {
+        if keys.iter().filter(|key| **key == gpu.key).count() > 1 {
+            gpu.key = format!("{}#{}", gpu.key, gpu.index);
+        }
+    }
+    gpus
+}
+
+struct Sample {
+    at: Instant,
+    gpus: Vec<GpuReading>,
+}
+
+/// 后台定时采集显卡数据，保存在环形缓冲区里，上报时给出最近一个窗口的统计
+#[derive(Clone)]
+pub struct GpuSampler {
+    window: Duration,
+    capacity: usize,
+    samples: Arc<Mutex<VecDeque<Sample>>>,
+}
+
+impl GpuSampler {
+    /// interval 为采样间隔，window 为统计窗口，缓冲区只保留一个窗口的采样
+    pub fn new(interval: Duration, window: Duration) -> Self {
+        let capacity = (window.as_millis() / interval.as_millis().max(1)) as usize + 1;
+        GpuSampler {
+            window,
+            capacity,
+            samples: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// 启动采样任务，每隔 interval 调用一次 registry.sample()，需要在 tokio 运行时中调用；
+    /// 各后端的 sample 会执行命令、读 sysfs，放到阻塞线程池里，不占用运行时的工作线程
+    pub fn spawn<R: Into<Arc<GpuRegistry>>>(registry: R, interval: Duration, window: Duration) -> Self {
+        let sampler = GpuSampler::new(interval, window);
+
+        let registry = registry.into();
+        let recorder = sampler.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                let registry = registry.clone();
+                match tokio::task::spawn_blocking(move || registry.sample().map_err(|err| err.to_string())).await {
+                    Ok(Ok(gpu_info)) => recorder.record(&gpu_info),
+                    Ok(Err(err)) => info!("显卡采样失败: {}", err),
+                    Err(err) => info!("显卡采样任务异常退出: {}", err),
+                }
+            }
+        });
+
+        sampler
+    }
+
+    /// 记录一次采样，缓冲区满时丢弃最旧的一条
+    pub fn record(&self, gpu_info: &[GpuInfo]) {
+        let mut samples = match self.samples.lock() {
+            Ok(samples) => samples,
+            Err(_) => return,
+        };
+        if samples.len() >= self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(Sample {
+            at: Instant::now(),
+            gpus: readings(gpu_info),
+        });
+    }
+
+    /// 最近一个窗口内每张显卡的统计，按显卡首次出现的顺序排列
+    pub fn stats(&self) -> Vec<GpuStats> {
+        let samples = match self.samples.lock() {
+            Ok(samples) => samples,
+            Err(_) => return vec![],
+        };
+
+        let now = Instant::now();
+        let readings: Vec<&GpuReading> = samples
+            .iter()
+            .filter(|sample| now.duration_since(sample.at) <= self.window)
+            .flat_map(|sample| sample.gpus.iter())
+            .collect();
+
+        let mut keys: Vec<&str> = vec![];
+        for reading in &readings {
+            if !keys.contains(&reading.key.as_str()) {
+                keys.push(&reading.key);
+            }
+        }
+
+        keys.into_iter()
+            .map(|key| {
+                let gpu: Vec<&GpuReading> = readings.iter().copied().filter(|reading| reading.key == key).collect();
+                let last = gpu[gpu.len() - 1];
+                GpuStats {
+                    index: last.index.clone(),
+                    uuid: last.uuid.clone(),
+                    vendor: last.vendor.clone(),
+                    gpu_bus_id: last.gpu_bus_id.clone(),
+                    samples: gpu.len(),
+                    window_seconds: self.window.as_secs(),
+                    utilization_percent: sample_stats(gpu.iter().filter_map(|reading| reading.utilization_percent)),
+                    memory_used_bytes: sample_stats(gpu.iter().filter_map(|reading| reading.memory_used_bytes)),
+                    temperature_celsius: sample_stats(gpu.iter().filter_map(|reading| reading.temperature_celsius)),
+                    power_draw_watts: sample_stats(gpu.iter().filter_map(|reading| reading.power_draw_watts)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// p95 取最近秩，即排序后第 ceil(0.95 * n) 个值
+fn sample_stats(values: impl Iterator<Item = f64>) -> Option<SampleStats> {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let rank = (values.len() as f64 * 0.95).ceil() as usize;
+    Some(SampleStats {
+        min: values[0],
+        avg: values.iter().sum::<f64>() / values.len() as f64,
+        max: values[values.len() - 1],
+        p95: values[rank.max(1) - 1],
+    })
+}
+
+/// 启动全局的显卡采样任务，重复调用不会启动第二个，与上报共用同一个 registry
+pub fn start_gpu_sampler(interval: Duration, window: Duration) {
+    SAMPLER.get_or_init(|| GpuSampler::spawn(GpuRegistry::shared(), interval, window));
+}
+
+/// 全局采样任务的统计，没有启动时为空
+pub fn gpu_stats() -> Vec<GpuStats> {
+    SAMPLER.get().map(|sampler| sampler.stats()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::pci::PciDevice;
+    use super::super::GpuProbe;
+
+    /// 记录调用次数的后端，每次采样利用率加 10
+    struct FakeProbe {
+        enumerated: Arc<AtomicUsize>,
+        sampled: Arc<AtomicUsize>,
+    }
+
+    impl GpuProbe for FakeProbe {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn vendor(&self) -> &'static str {
+            "fake"
+        }
+
+        fn detect(&self, _devices: &[PciDevice]) -> bool {
+            true
+        }
+
+        fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+            self.enumerated.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![])
+        }
+
+        fn sample(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+            let count = self.sampled.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![GpuInfo {
+                index: "0".to_string(),
+                uuid: "GPU-fake".to_string(),
+                utilization_percent: Some(count as f32 * 10.0),
+                ..Default::default()
+            }])
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn samples_on_interval_within_window() {
+        let enumerated = Arc::new(AtomicUsize::new(0));
+        let sampled = Arc::new(AtomicUsize::new(0));
+        let registry = GpuRegistry::empty().register(FakeProbe {
+            enumerated: enumerated.clone(),
+            sampled: sampled.clone(),
+        });
+        let sampler = GpuSampler::spawn(registry, Duration::from_secs(1), Duration::from_secs(10));
+
+        // 第 0、1、2、3 秒各采样一次
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        assert_eq!(sampled.load(Ordering::SeqCst), 4);
+        assert_eq!(enumerated.load(Ordering::SeqCst), 0);
+
+        let stats = sampler.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].uuid, "GPU-fake");
+        assert_eq!(stats[0].samples, 4);
+        assert_eq!(stats[0].window_seconds, 10);
+        assert_eq!(stats[0].utilization_percent, Some(SampleStats { min: 0.0, avg: 15.0, max: 30.0, p95: 30.0 }));
+        assert_eq!(stats[0].temperature_celsius, None);
+
+        // 第 23.5 秒时窗口内只剩第 14 到 23 秒的 10 次
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        assert_eq!(sampled.load(Ordering::SeqCst), 24);
+        let stats = sampler.stats();
+        assert_eq!(stats[0].samples, 10);
+        assert_eq!(stats[0].utilization_percent.as_ref().map(|stats| stats.min), Some(140.0));
+        assert_eq!(stats[0].utilization_percent.as_ref().map(|stats| stats.max), Some(230.0));
+    }
+
+    /// 返回固定读数的后端，每张卡的利用率固定
+    struct FixedProbe {
+        vendor: &'static str,
+        gpus: Vec<(&'static str, &'static str, f32)>,
+    }
+
+    impl GpuProbe for FixedProbe {
+        fn name(&self) -> &'static str {
+            self.vendor
+        }
+
+        fn vendor(&self) -> &'static str {
+            self.vendor
+        }
+
+        fn detect(&self, _devices: &[PciDevice]) -> bool {
+            true
+        }
+
+        fn enumerate(&self) -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+            Ok(self.gpus.iter().map(|(index, bus_id, utilization)| GpuInfo {
+                index: index.to_string(),
+                gpu_bus_id: bus_id.to_string(),
+                utilization_percent: Some(*utilization),
+                ..Default::default()
+            }).collect())
+        }
+    }
+
+    #[test]
+    fn same_index_from_different_backends_is_kept_apart() {
+        let registry = GpuRegistry::empty()
+            .register(FixedProbe { vendor: "nvidia", gpus: vec![("0", "00000000:3B:00.0", 90.0)] })
+            .register(FixedProbe { vendor: "intel", gpus: vec![("0", "0000:00:02.0", 10.0)] })
+            // 两个后端都拿不到地址时按厂商和序号区分
+            .register(FixedProbe { vendor: "amd", gpus: vec![("0", "", 50.0)] })
+            .register(FixedProbe { vendor: "huawei", gpus: vec![("0", "", 30.0)] });
+
+        let sampler = GpuSampler::new(Duration::from_secs(1), Duration::from_secs(10));
+        sampler.record(&registry.sample().unwrap());
+        sampler.record(&registry.sample().unwrap());
+
+        let stats = sampler.stats();
+        let keys: Vec<(&str, &str, usize)> = stats
+            .iter()
+            .map(|stats| (stats.vendor.as_str(), stats.gpu_bus_id.as_str(), stats.samples))
+            .collect();
+        assert_eq!(keys, vec![
+            ("nvidia", "00000000:3B:00.0", 2),
+            ("intel", "0000:00:02.0", 2),
+            ("amd", "", 2),
+            ("huawei", "", 2),
+        ]);
+        assert_eq!(stats[0].utilization_percent.as_ref().map(|stats| stats.max), Some(90.0));
+        assert_eq!(stats[1].utilization_percent.as_ref().map(|stats| stats.max), Some(10.0));
+    }
+
+    #[test]
+    fn chips_sharing_an_address_are_kept_apart() {
+        // 310P 一张卡两个芯片
+        let chip = |index: &str, utilization: f32| GpuInfo {
+            index: index.to_string(),
+            gpu_bus_id: "0000:01:00.0".to_string(),
+            vendor: "huawei".to_string(),
+            utilization_percent: Some(utilization),
+            ..Default::default()
+        };
+
+        let sampler = GpuSampler::new(Duration::from_secs(1), Duration::from_secs(10));
+        sampler.record(&[chip("1", 0.0), chip("1-1", 12.0)]);
+
+        let stats = sampler.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].index, "1-1");
+        assert_eq!(stats[1].utilization_percent.as_ref().map(|stats| stats.max), Some(12.0));
+    }
+
+    #[test]
+    fn p95_uses_nearest_rank() {
+        let stats = sample_stats((1..=20).map(f64::from)).unwrap();
+        assert_eq!(stats.p95, 19.0);
+        assert_eq!(stats.avg, 10.5);
+        assert!(sample_stats(std::iter::empty()).is_none());
+    }
+}
@@ -0,0 +1,270 @@
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use std::error::Error;
+use std::fmt;
+
+use crate::sysfs;
+use super::nvml::Nvml;
+
+/// 两张显卡之间的连接方式，与 nvidia-smi topo -m 的写法一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkType {
+    /// X，自身
+    SameDevice,
+    /// NV#，通过 # 条 NVLink 直连
+    NvLink(u32),
+    /// PIX，经过一个 PCIe 交换机
+    Pix,
+    /// PXB，经过多个 PCIe 交换机，不经过 CPU 的 host bridge
+    Pxb,
+    /// PHB，经过 CPU 的 host bridge
+    Phb,
+    /// NODE，同一个 NUMA 节点内的不同 host bridge
+    Node,
+    /// SYS，跨 NUMA 节点（QPI/UPI）
+    Sys,
+    /// 无法识别的写法，原样保留
+    Other(String),
+}
+
+impl LinkType {
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "X" => LinkType::SameDevice,
+            "PIX" => LinkType::Pix,
+            "PXB" => LinkType::Pxb,
+            "PHB" => LinkType::Phb,
+            "NODE" => LinkType::Node,
+            "SYS" | "SOC" => LinkType::Sys,
+            value => match value.strip_prefix("NV").and_then(|count| count.parse::<u32>().ok()) {
+                Some(count) => LinkType::NvLink(count),
+                None => LinkType::Other(value.to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for LinkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkType::SameDevice => write!(f, "X"),
+            LinkType::NvLink(count) => write!(f, "NV{}", count),
+            LinkType::Pix => write!(f, "PIX"),
+            LinkType::Pxb => write!(f, "PXB"),
+            LinkType::Phb => write!(f, "PHB"),
+            LinkType::Node => write!(f, "NODE"),
+            LinkType::Sys => write!(f, "SYS"),
+            LinkType::Other(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl Serialize for LinkType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for LinkType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(LinkType::parse(&String::deserialize(deserializer)?))
+    }
+}
+
+/// 单张显卡的 CPU 和 NUMA 亲和性
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GpuAffinity {
+    /// 如 GPU0
+    pub gpu: String,
+    /// 亲和的 CPU 编号
+    pub cpus: Vec<u32>,
+    pub numa_node: Option<u32>,
+}
+
+/// 显卡互联拓扑
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GpuTopology {
+    /// 矩阵的行列顺序，如 ["GPU0", "GPU1"]
+    pub gpus: Vec<String>,
+    /// links[i][j] 是 gpus[i] 到 gpus[j] 的连接方式
+    pub links: Vec<Vec<LinkType>>,
+    pub affinity: Vec<GpuAffinity>,
+}
+
+impl GpuTopology {
+    pub fn link(&self, from: usize, to: usize) -> Option<&LinkType> {
+        self.links.get(from)?.get(to)
+    }
+}
+
+/// 获取 N 卡拓扑，优先使用 NVML，否则解析 nvidia-smi topo -m，没有 N 卡时返回 None
+pub fn gpu_topology() -> Result<Option<GpuTopology>, Box<dyn Error>> {
+    if let Some(topology) = Nvml::get().and_then(|nvml| nvml.topology()) {
+        return Ok(Some(topology));
+    }
+
+    let output = match std::process::Command::new("nvidia-smi").arg("topo").arg("-m").output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    if !output.status.success() {
+        return Err(format!("nvidia-smi topo -m 执行失败: {}", String::from_utf8_lossy(&output.stdout).trim()).into());
+    }
+
+    Ok(Some(parse_topo_matrix(&String::from_utf8_lossy(&output.stdout))))
+}
+
+/// 解析 nvidia-smi topo -m 的输出：
+///
+/// ```text
+///         GPU0    GPU1    NIC0    CPU Affinity    NUMA Affinity   GPU NUMA ID
+/// GPU0     X      NV12    SYS     0-31,64-95      0               N/A
+/// GPU1    NV12     X      SYS     0-31,64-95      0               N/A
+/// NIC0    SYS     SYS      X
+/// ```
+///
+/// 只保留显卡之间的连接，网卡的行和列忽略
+pub fn parse_topo_matrix(output: &str) -> GpuTopology {
+    let output = strip_ansi(output);
+    let mut lines = output.lines().filter(|line| !line.trim().is_empty());
+
+    let header: Vec<String> = match lines.next() {
+        Some(header) => split_columns(header),
+        None => return GpuTopology::default(),
+    };
+    let gpu_columns: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| is_gpu(name))
+        .map(|(i, _)| i)
+        .collect();
+    let cpu_column = header.iter().position(|name| name == "CPU Affinity");
+    let numa_column = header.iter().position(|name| name == "NUMA Affinity");
+
+    let mut topology = GpuTopology::default();
+
+    for line in lines {
+        // 后面是图例
+        if line.starts_with("Legend") {
+            break;
+        }
+
+        let cells = split_columns(line);
+        let label = match cells.first() {
+            Some(label) if is_gpu(label) => label.clone(),
+            _ => continue,
+        };
+        // 行首是设备名，其余单元格与表头一一对应
+        let cell = |column: usize| cells.get(column + 1).map(|cell| cell.as_str());
+
+        topology.links.push(gpu_columns.iter().map(|&column| LinkType::parse(cell(column).unwrap_or(""))).collect());
+        topology.affinity.push(GpuAffinity {
+            gpu: label.clone(),
+            cpus: cpu_column.and_then(cell).map(sysfs::parse_cpu_list).unwrap_or_default(),
+            numa_node: numa_column.and_then(cell).and_then(|value| value.parse::<u32>().ok()),
+        });
+        topology.gpus.push(label);
+    }
+
+    topology
+}
+
+fn is_gpu(name: &str) -> bool {
+    name.strip_prefix("GPU").is_some_and(|index| index.parse::<u32>().is_ok())
+}
+
+/// 列之间用制表符分隔，列名 CPU Affinity 本身带空格
+fn split_columns(line: &str) -> Vec<String> {
+    line.split('\t')
+        .map(|cell| cell.trim())
+        .filter(|cell| !cell.is_empty())
+        .map(|cell| cell.to_string())
+        .collect()
+}
+
+/// 新版本的表头带下划线等终端控制符
+fn strip_ansi(output: &str) -> String {
+    let re = regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+    re.replace_all(output, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // nvidia-smi topo -m，535 之后的驱动表头带下划线控制符
+    const TOPO: &str = "\t\x1b[4mGPU0\tGPU1\tGPU2\tGPU3\tNIC0\tNIC1\tCPU Affinity\tNUMA Affinity\tGPU NUMA ID\x1b[0m
+GPU0\t X \tNV12\tPXB\tSYS\tPIX\tSYS\t0-31,64-95\t0\t\tN/A
+GPU1\tNV12\t X \tPHB\tSYS\tNODE\tSYS\t0-31,64-95\t0\t\tN/A
+GPU2\tPXB\tPHB\t X \tNV4\tSYS\tNODE\t32-63,96-127\t1\t\tN/A
+GPU3\tSYS\tSYS\tNV4\t X \tSYS\tPIX\t32-63,96-127\t1\t\tN/A
+NIC0\tPIX\tNODE\tSYS\tSYS\t X \tSYS
+NIC1\tSYS\tSYS\tNODE\tPIX\tSYS\t X
+
+Legend:
+
+  X    = Self
+  SYS  = Connection traversing PCIe as well as the SMP interconnect between NUMA nodes (e.g., QPI/UPI)
+  NODE = Connection traversing PCIe as well as the interconnect between PCIe Host Bridges within a NUMA node
+  PHB  = Connection traversing PCIe as well as a PCIe Host Bridge (typically the CPU)
+  PXB  = Connection traversing multiple PCIe bridges (without traversing the PCIe Host Bridge)
+  PIX  = Connection traversing at most a single PCIe bridge
+  NV#  = Connection traversing a bonded set of # NVLinks
+
+NIC Legend:
+
+  NIC0: mlx5_0
+  NIC1: mlx5_1
+";
+
+    #[test]
+    fn parses_topo_matrix() {
+        let topology = parse_topo_matrix(TOPO);
+        assert_eq!(topology.gpus, vec!["GPU0", "GPU1", "GPU2", "GPU3"]);
+        assert_eq!(topology.links.len(), 4);
+        assert!(topology.links.iter().all(|row| row.len() == 4));
+
+        assert_eq!(topology.link(0, 0), Some(&LinkType::SameDevice));
+        assert_eq!(topology.link(0, 1), Some(&LinkType::NvLink(12)));
+        assert_eq!(topology.link(0, 2), Some(&LinkType::Pxb));
+        assert_eq!(topology.link(0, 3), Some(&LinkType::Sys));
+        assert_eq!(topology.link(1, 2), Some(&LinkType::Phb));
+        assert_eq!(topology.link(2, 3), Some(&LinkType::NvLink(4)));
+        assert_eq!(topology.link(3, 2), Some(&LinkType::NvLink(4)));
+        assert_eq!(topology.link(4, 0), None);
+
+        assert_eq!(topology.affinity[0].gpu, "GPU0");
+        assert_eq!(topology.affinity[0].cpus.len(), 64);
+        assert_eq!(topology.affinity[0].cpus[32], 64);
+        assert_eq!(topology.affinity[0].numa_node, Some(0));
+        assert_eq!(topology.affinity[3].cpus.first(), Some(&32));
+        assert_eq!(topology.affinity[3].numa_node, Some(1));
+    }
+
+    #[test]
+    fn parses_link_types() {
+        let cases = [
+            ("X", LinkType::SameDevice),
+            ("NV1", LinkType::NvLink(1)),
+            ("NV18", LinkType::NvLink(18)),
+            ("PIX", LinkType::Pix),
+            ("PXB", LinkType::Pxb),
+            ("PHB", LinkType::Phb),
+            ("NODE", LinkType::Node),
+            ("SYS", LinkType::Sys),
+            ("SOC", LinkType::Sys),
+            ("NV", LinkType::Other("NV".to_string())),
+        ];
+        for (value, link) in cases {
+            assert_eq!(LinkType::parse(value), link, "{}", value);
+        }
+        assert_eq!(LinkType::NvLink(12).to_string(), "NV12");
+        assert_eq!(serde_json::to_string(&LinkType::Node).unwrap(), "\"NODE\"");
+    }
+
+    #[test]
+    fn empty_output() {
+        assert_eq!(parse_topo_matrix(""), GpuTopology::default());
+    }
+}
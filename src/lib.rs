@@ -1,4 +1,5 @@
 use serde::{Serialize,Deserialize};
+use serde::de::DeserializeOwned;
 use serde_json::{json};
 use serde_json::Value;
 use tokio::process::Command;
@@ -7,67 +8,297 @@ use std::error::Error;
 #[macro_use]
 extern crate wei_log;
 
-#[derive(Serialize, Debug)]
+mod error;
+pub use error::{HardwareError, ProbeError};
+
+mod sysfs;
+pub mod pci;
+pub use pci::{PciAddress, PciDevice};
+pub mod cpu;
+pub use cpu::CpuSysfs;
+pub mod dmi;
+pub use dmi::{DmiSysfs, SystemInfo};
+pub mod meminfo;
+pub use meminfo::parse_meminfo;
+pub mod smbios;
+pub use smbios::MemoryModule;
+pub mod disk;
+pub use disk::{BlockSysfs, DiskConfig, PhysicalDisk};
+pub mod numa;
+pub use numa::{NumaDevice, NumaNode, NumaSysfs};
+pub mod gpu;
+mod fingerprint;
+pub use fingerprint::{FingerprintComponent, MachineFingerprint, machine_fingerprint, acknowledge_uuid_change};
+pub use gpu::{GpuDetails, MigInstance, MigComputeInstance, GpuProbe, GpuSampler, GpuStats, start_gpu_sampler, GpuControl, GpuTarget, GpuSetting, GpuProcess, GpuRegistry, GpuTopology, nvidia, parse_nvidia_csv, legacy_memory, legacy_power, legacy_temperature};
+
+/// 上报给服务器的完整数据，即 `all()` 的返回值
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Report {
+    pub hardware: HardwareInfo,
+    pub network: Vec<NetInfo>,
+    /// wei-docker image_list_full 的原始输出
+    pub images: Value,
+    /// wei-docker container_ps 的原始输出
+    pub containers: Value,
+    /// 显卡上的计算进程及其所在容器
+    #[serde(default)]
+    pub gpu_processes: Vec<GpuProcess>,
+    /// 后台采样得到的最近一个上报窗口内的显卡统计，没有启动采样时为空
+    #[serde(default)]
+    pub gpu_stats: Vec<GpuStats>,
+    pub model: Vec<FileInfo>,
+    pub model_timestamp: u64,
+    pub dataset: Vec<FileInfo>,
+    pub dataset_timestamp: u64,
+    /// 公网 IP 查询结果，格式由查询站点决定
+    pub ip: Value,
+    pub docker_installed: bool,
+    pub host_service_up: String,
+    pub host_service_up_default: String,
+    pub tech_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ProbeError>,
+}
+
+impl Report {
+    /// 序列化为上报用的 JSON，字段按键名排序，与之前 json! 拼出来的格式一致
+    pub fn to_json(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(data) => data.to_string(),
+            Err(_) => "{}".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HardwareInfo {
-    os_info: OsInfo,
-    cpu_info: CpuInfo,
-    gpu_info: Vec<GpuInfo>,
-    mem_info: MemoryInfo,
-    disks_info: Vec<DiskInfo>,
+    pub os_info: OsInfo,
+    pub cpu_info: CpuInfo,
+    /// 整机厂商、主板和 BIOS
+    #[serde(default)]
+    pub system_info: SystemInfo,
+    pub gpu_info: Vec<GpuInfo>,
+    /// 机器指纹，Linux 上 cpu_info.uuid 为空时也使用它
+    #[serde(default)]
+    pub fingerprint: MachineFingerprint,
+    /// 多张 N 卡之间的互联拓扑，没有 N 卡时为 None
+    #[serde(default)]
+    pub gpu_topology: Option<GpuTopology>,
+    pub mem_info: MemoryInfo,
+    /// 内存插槽，包括空插槽
+    #[serde(default)]
+    pub memory_modules: Vec<MemoryModule>,
+    /// NUMA 节点及其 CPU、内存和设备，单节点或没有开启 NUMA 时可能为空
+    #[serde(default)]
+    pub numa: Vec<NumaNode>,
+    /// 去重后的真实挂载点，容量只计算一次
+    pub disks_info: Vec<DiskInfo>,
+    /// 被过滤掉的虚拟文件系统和重复的 bind mount
+    #[serde(default)]
+    pub ignored_mounts: Vec<DiskInfo>,
+    #[serde(default)]
+    pub physical_disks: Vec<PhysicalDisk>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ProbeError>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OsInfo {
-    hostname: String,
-    os_type: String,
-    version: String,
-    bitness: String
+    pub hostname: String,
+    pub os_type: String,
+    pub version: String,
+    pub bitness: String
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct CpuInfo {
-    uuid: String,
-    name: String,
-    num: u32,
-    speed: u64,
-    core_num: u32
+    pub uuid: String,
+    pub name: String,
+    /// CPU 插槽数
+    pub num: u32,
+    /// 旧字段，Linux 上为 MHz × 1000，Windows 上为 MHz，新代码请使用 max_mhz / cur_mhz
+    pub speed: u64,
+    /// 每个插槽的物理核心数
+    pub core_num: u32,
+    #[serde(default)]
+    pub sockets: u32,
+    /// 所有插槽的物理核心总数
+    #[serde(default)]
+    pub physical_cores: u32,
+    /// 逻辑 CPU 数，开启超线程时是物理核心数的两倍
+    #[serde(default)]
+    pub threads: u32,
+    /// GenuineIntel、AuthenticAMD、ARM、HiSilicon 等
+    #[serde(default)]
+    pub vendor: String,
+    #[serde(default)]
+    pub family: Option<u32>,
+    /// x86 为 model，ARM 为 CPU part
+    #[serde(default)]
+    pub model: Option<u32>,
+    /// x86 为 stepping，ARM 为 CPU revision
+    #[serde(default)]
+    pub stepping: Option<u32>,
+    #[serde(default)]
+    pub microcode: Option<String>,
+    /// 各级缓存单个实例的大小，单位字节
+    #[serde(default)]
+    pub l1d_cache_bytes: Option<u64>,
+    #[serde(default)]
+    pub l1i_cache_bytes: Option<u64>,
+    #[serde(default)]
+    pub l2_cache_bytes: Option<u64>,
+    #[serde(default)]
+    pub l3_cache_bytes: Option<u64>,
+    /// 与机器学习相关的指令集，如 avx2、avx512f、amx_tile、sve
+    #[serde(default)]
+    pub ml_flags: Vec<String>,
+    /// 所有核心中最高的当前频率，单位 MHz
+    #[serde(default)]
+    pub cur_mhz: Option<u32>,
+    /// 硬件支持的最低频率，单位 MHz
+    #[serde(default)]
+    pub min_mhz: Option<u32>,
+    /// 硬件支持的最高频率，单位 MHz
+    #[serde(default)]
+    pub max_mhz: Option<u32>,
+    /// cpu0 的调频策略
+    #[serde(default)]
+    pub governor: Option<String>,
+    #[serde(default)]
+    pub core_frequencies: Vec<cpu::CoreFrequency>,
 }
 
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct GpuInfo {
-    index: String,
-    name: String,
-    uuid: String,
-    gpu_bus_id: String,
-    memory_used: String,
-    memory_total: String,
-    temperature: String,
-    power_draw: String,
+    pub index: String,
+    pub name: String,
+    pub uuid: String,
+    pub gpu_bus_id: String,
+    /// nvidia-smi 原始格式，如 "1234 MiB"，保留给旧的服务端使用
+    pub memory_used: String,
+    pub memory_total: String,
+    pub temperature: String,
+    pub power_draw: String,
+    /// 显存已用，单位字节，N/A 时为 None
+    #[serde(default)]
+    pub memory_used_bytes: Option<u64>,
+    /// 显存总量，单位字节
+    #[serde(default)]
+    pub memory_total_bytes: Option<u64>,
+    /// 温度，单位摄氏度
+    #[serde(default)]
+    pub temperature_celsius: Option<f32>,
+    /// 功耗，单位瓦
+    #[serde(default)]
+    pub power_draw_watts: Option<f32>,
+    /// GPU 使用率，百分比
+    #[serde(default)]
+    pub utilization_percent: Option<f32>,
+    /// 设备健康状态，目前只有昇腾 NPU 提供
+    #[serde(default)]
+    pub health: Option<String>,
+    /// 当前核心频率，单位 MHz
+    #[serde(default)]
+    pub clock_mhz: Option<u32>,
+    /// 最大核心频率，单位 MHz
+    #[serde(default)]
+    pub max_clock_mhz: Option<u32>,
+    /// nvidia-smi -q -x 中的详细信息
+    #[serde(default)]
+    pub details: Option<GpuDetails>,
+    /// 开启 MIG 时切分出的 GPU 实例
+    #[serde(default)]
+    pub mig_instances: Vec<MigInstance>,
+    /// 厂商：nvidia、amd、huawei、intel
+    #[serde(default)]
+    pub vendor: String,
+}
+
+impl GpuInfo {
+    /// 解析 gpu_bus_id，不同后端的写法不一样，比较设备时使用这个
+    pub fn pci_address(&self) -> Option<PciAddress> {
+        PciAddress::parse(&self.gpu_bus_id)
+    }
 }
 
-#[derive(Serialize, Debug)]
+/// 内存信息，除 hugepages_total / hugepages_free 为页数外，单位均为字节
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct MemoryInfo {
-    total: u64,
-    free: u64,
-    buffers: u64,
-    cached: u64,
+    pub total: u64,
+    pub free: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    /// 不换出就能分配给新进程的内存，比 free 更能反映剩余内存
+    #[serde(default)]
+    pub available_bytes: Option<u64>,
+    /// tmpfs 和共享内存
+    #[serde(default)]
+    pub shared_bytes: Option<u64>,
+    #[serde(default)]
+    pub swap_total_bytes: Option<u64>,
+    #[serde(default)]
+    pub swap_free_bytes: Option<u64>,
+    #[serde(default)]
+    pub hugepages_total: Option<u64>,
+    #[serde(default)]
+    pub hugepages_free: Option<u64>,
+    #[serde(default)]
+    pub hugepage_size_bytes: Option<u64>,
+    /// 已承诺分配的内存，即 Committed_AS
+    #[serde(default)]
+    pub committed_bytes: Option<u64>,
+    #[serde(default)]
+    pub commit_limit_bytes: Option<u64>,
 }
 
-// 需要知道是什么类型的盘，ssd还是nvme，sata
-#[derive(Serialize, Deserialize, Debug)]
+/// Linux 上每个挂载点一条，MediaType、Model、Size、kind 是所在物理磁盘的信息，
+/// mount_kind 区分真实挂载、虚拟文件系统和重复挂载；
+/// Windows 上是 Get-PhysicalDisk 的结果，每块磁盘一条。大小的单位均为字节
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct DiskInfo {
-    #[serde(rename = "MediaType")]
-    media_type: String,
-    #[serde(rename = "Model")]
-    name: String,
-    #[serde(rename = "Size")]
-    size: String,
-    mount_point: String,
-    available_space: String,
-    file_system: String,
-    total_space: String,
-    kind: String,
+    /// SSD、HDD 或 Unspecified
+    #[serde(rename = "MediaType", default)]
+    pub media_type: String,
+    #[serde(rename = "Model", default)]
+    pub name: String,
+    #[serde(rename = "Size", default)]
+    pub size: u64,
+    #[serde(default)]
+    pub mount_point: String,
+    #[serde(default)]
+    pub available_space: u64,
+    #[serde(default)]
+    pub file_system: String,
+    #[serde(default)]
+    pub total_space: u64,
+    /// 接口类型：nvme、sata、sas、usb、virtio、mmc
+    #[serde(default)]
+    pub kind: String,
+    /// 挂载的块设备，如 /dev/nvme0n1p1
+    #[serde(default)]
+    pub device: String,
+    /// 所在物理磁盘，对应 physical_disks 中的 name
+    #[serde(default)]
+    pub disk: String,
+    /// 挂载的设备号，如 "259:1"，用于去重
+    #[serde(default)]
+    pub major_minor: String,
+    /// real、virtual 或 bind，Windows 上为空
+    #[serde(default)]
+    pub mount_kind: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetInfo {
+    pub name: String,
+    pub status: String,
+    pub mac: String,
+    /// Linux 上是第一个地址；Windows 上原样保留 PowerShell 的输出，有多个 IPv4 地址时是数组
+    pub ip: Value,
+    pub received: u64,
+    pub sent: u64,
 }
 
 pub async fn uuid() -> String {
@@ -79,7 +310,7 @@ pub async fn uuid() -> String {
     }
 }
 
-pub async fn all() -> String {
+pub async fn all() -> Report {
     info!("check: hardware");
     // let mut uptime = 10000.0;
     // match uptime_lib::get() {
@@ -91,125 +322,187 @@ pub async fn all() -> String {
     //     }
     // }
 
-    let hardware_path = format!("{}cache/hardware.json",wei_env::home_dir().unwrap());
-    let mut hardware = read_file_if_recent(hardware_path.clone(), 30 * 60).unwrap();
-    if hardware == "" {
-        hardware = info().await;
-        write_to_file(hardware_path.clone(), &hardware).unwrap();
-    }
-    let hardware: serde_json::Value = match serde_json::from_str(&hardware) {
-        Ok(data) => data,
-        Err(_) => {
-            let data:Value = serde_json::from_str(&info().await).unwrap();
-            write_to_file(hardware_path, &data.to_string()).unwrap();
-            data
-        },
+    let mut errors: Vec<ProbeError> = vec![];
+
+    // 有探测项失败时照样写缓存，之后每次只重新探测失败的部分
+    let hardware: HardwareInfo = match read_cache::<HardwareInfo>("hardware.json", 30 * 60) {
+        Some(mut hardware) => {
+            if !hardware.errors.is_empty() && retry_failed(&mut hardware).await {
+                write_cache("hardware.json", &hardware);
+            }
+            hardware
+        }
+        None => {
+            let hardware = info().await;
+            write_cache("hardware.json", &hardware);
+            hardware
+        }
     };
 
     info!("check: net");
-    let net_path = format!("{}cache/net.json",wei_env::home_dir().unwrap());
-    let mut net = read_file_if_recent(net_path.clone(), 30 * 60).unwrap();
-    if net == "" {
-        net = get_net_info().unwrap();
-        write_to_file(net_path, &net).unwrap();
-    }
-    let net: serde_json::Value = match serde_json::from_str(&net) {
-        Ok(data) => data,
-        Err(_) => serde_json::from_str(&get_net_info().unwrap()).unwrap()
-    };
+    let net = cached_probe("net.json", 30 * 60, get_net_info, HardwareError::Network);
+    let net = section(net, vec![], &mut errors);
 
     info!("check: model");
-    let model_path = format!("{}cache/model.json",wei_env::home_dir().unwrap());
-    let mut model = read_file_if_recent(model_path.clone(), 10 * 60).unwrap();
-    if model == "" {
-        model = get_file_info("model".to_string());
-        write_to_file(model_path.clone(), &model).unwrap();
-    }
-    let model: serde_json::Value = match serde_json::from_str(&model) {
-        Ok(data) => data,
-        Err(_) => serde_json::from_str(&get_file_info("model".to_string())).unwrap()
-    };
+    let model = cached_probe("model.json", 10 * 60, || get_file_info("model".to_string()), |cause| {
+        HardwareError::Files { dir: "model".to_string(), cause }
+    });
+    let model = section(model, vec![], &mut errors);
 
     info!("check: mode.json timestamp");
+    let model_path = cache_path("model.json");
     let model_json_timestamp = wei_file::get_timestamp(&model_path).unwrap_or(0);
 
     
     info!("check: dataset");
-    let dataset_path = format!("{}cache/dataset.json",wei_env::home_dir().unwrap());
-    let mut dataset = read_file_if_recent(dataset_path.clone(), 10 * 60).unwrap();
-    if dataset == "" {
-        dataset = get_file_info("dataset".to_string());
-        write_to_file(dataset_path.clone(), &dataset).unwrap();
-    }
-    let dataset: serde_json::Value = match serde_json::from_str(&dataset) {
-        Ok(data) => data,
-        Err(_) => serde_json::from_str(&get_file_info("dataset".to_string())).unwrap()
-    };
+    let dataset = cached_probe("dataset.json", 10 * 60, || get_file_info("dataset".to_string()), |cause| {
+        HardwareError::Files { dir: "dataset".to_string(), cause }
+    });
+    let dataset = section(dataset, vec![], &mut errors);
 
     info!("check: dataset.json timestamp");
     let dataset_json_timestamp = wei_file::get_timestamp(&model_path).unwrap_or(0);
 
     info!("check: ip");
-    let ip_path = format!("{}cache/ip.json",wei_env::home_dir().unwrap());
-    let mut ip = read_file_if_recent(ip_path.clone(), 30 * 60).unwrap();
-    if ip == "" {
-        ip = get_ip_info().await;
-        write_to_file(ip_path, &ip).unwrap();
-    }
-    let ip: serde_json::Value = match serde_json::from_str(&ip) {
-        Ok(data) => data,
-        Err(_) => match serde_json::from_str(&get_ip_info().await) {
-            Ok(data) => data,
-            Err(_) => serde_json::from_str("{}").unwrap()
+    let ip = match read_cache("ip.json", 30 * 60) {
+        Some(data) => Ok(data),
+        None => {
+            let ip = get_ip_info().await;
+            match serde_json::from_str::<Value>(&ip) {
+                Ok(data) => {
+                    write_cache("ip.json", &data);
+                    Ok(data)
+                }
+                Err(e) => Err(HardwareError::PublicIp(e.to_string())),
+            }
         }
     };
+    let ip = section(ip, json!({}), &mut errors);
     
 
     info!("check: docker images");
-    let images: serde_json::Value = serde_json::from_str(&wei_run::run("wei-docker", vec!["image_list_full"]).unwrap()).unwrap();
+    let images = section(docker("image_list_full"), json!([]), &mut errors);
     info!("check: containers");
-    let containers: serde_json::Value = serde_json::from_str(&wei_run::run("wei-docker", vec!["container_ps"]).unwrap()).unwrap();
+    let containers = section(docker("container_ps"), json!([]), &mut errors);
+
+    info!("check: gpu processes");
+    let gpu_containers = containers.clone();
+    let gpu_processes = blocking(move || gpu::gpu_processes(&gpu_containers))
+        .await
+        .map_err(|e| HardwareError::Gpu(e.to_string()));
+    let gpu_processes = section(gpu_processes, vec![], &mut errors);
+    let gpu_stats = gpu::gpu_stats();
 
     info!("check: docker installed");
-    let docker_status = wei_run::run("wei-docker", vec!["is_installed"]).unwrap();
-    let docker_status: serde_json::Value = serde_json::from_str(&docker_status).unwrap();
+    let docker_status = section(docker("is_installed"), json!({}), &mut errors);
     let docker_is_installed = docker_status["is_installed"].as_bool().unwrap_or(false);
 
     // host_service_up docker 是否已经开启0没1开
     info!("check: docker service is started");
-    let docker_status = wei_run::run("wei-docker", vec!["is_started"]).unwrap();
-    let docker_status: serde_json::Value = serde_json::from_str(&docker_status).unwrap();
+    let docker_status = section(docker("is_started"), json!({}), &mut errors);
     let docker_is_started = docker_status["is_start"].as_str().unwrap_or("0");
 
     //host_service_up_default docker 默认开启吗0不1开
     info!("check: docker service is autorun?");
-    let docker_status = wei_run::run("wei-docker", vec!["is_autorun"]).unwrap();
-    let docker_status: serde_json::Value = serde_json::from_str(&docker_status).unwrap();
+    let docker_status = section(docker("is_autorun"), json!({}), &mut errors);
     let docker_is_autorun = docker_status["data"].as_str().unwrap_or("0");
-    let tech_type = format!("{}/tech_type.dat",wei_env::home_dir().unwrap());
-    let tech_type = std::fs::read_to_string(tech_type).unwrap_or("docker".to_string());
+    let tech_type = match wei_env::home_dir() {
+        Ok(home_dir) => std::fs::read_to_string(format!("{}/tech_type.dat", home_dir)).unwrap_or("docker".to_string()),
+        Err(_) => "docker".to_string(),
+    };
     let tech_type = tech_type.trim();
 
-    let data = serde_json::json!({
-        "hardware" : hardware,
-        "network" : net,
-        "images" : images,
-        "containers" : containers,
-        "model" : model,
-        "model_timestamp" : model_json_timestamp,
-        "dataset" : dataset,
-        "dataset_timestamp" : dataset_json_timestamp,
-        "ip" : ip,
-        "docker_installed": docker_is_installed,
-        "host_service_up": docker_is_started,
-        "host_service_up_default": docker_is_autorun,
-        "tech_type": tech_type
-    });
+    Report {
+        hardware,
+        network: net,
+        images,
+        containers,
+        gpu_processes,
+        gpu_stats,
+        model,
+        model_timestamp: model_json_timestamp,
+        dataset,
+        dataset_timestamp: dataset_json_timestamp,
+        ip,
+        docker_installed: docker_is_installed,
+        host_service_up: docker_is_started.to_string(),
+        host_service_up_default: docker_is_autorun.to_string(),
+        tech_type: tech_type.to_string(),
+        errors,
+    }
+}
+
+/// 探测失败时记录错误，并返回该段的默认值，保证上报数据完整
+fn section<T>(result: Result<T, HardwareError>, default: T, errors: &mut Vec<ProbeError>) -> T {
+    match result {
+        Ok(data) => data,
+        Err(err) => {
+            info!("{}", err);
+            errors.push(ProbeError::from(&err));
+            default
+        }
+    }
+}
+
+fn docker(command: &str) -> Result<Value, HardwareError> {
+    let output = wei_run::run("wei-docker", vec![command]).map_err(|e| HardwareError::Docker {
+        command: command.to_string(),
+        cause: e.to_string(),
+    })?;
+
+    serde_json::from_str(&output).map_err(|e| HardwareError::Docker {
+        command: command.to_string(),
+        cause: format!("{}, output: {}", e, output.trim()),
+    })
+}
+
+fn cache_path(name: &str) -> String {
+    match wei_env::home_dir() {
+        Ok(home_dir) => format!("{}cache/{}", home_dir, name),
+        Err(_) => format!("cache/{}", name),
+    }
+}
+
+/// 读取未过期的缓存，缓存不存在、过期或者无法解析时返回 None
+fn read_cache<T: DeserializeOwned>(name: &str, max_age_secs: u64) -> Option<T> {
+    let path = cache_path(name);
+    match read_file_if_recent(&path, max_age_secs) {
+        Ok(data) if data.is_empty() => None,
+        Ok(data) => serde_json::from_str(&data).ok(),
+        Err(err) => {
+            info!("读取缓存失败 {}: {}", path, err);
+            None
+        }
+    }
+}
+
+fn write_cache<T: Serialize>(name: &str, data: &T) {
+    let path = cache_path(name);
+    let data = match serde_json::to_string(data) {
+        Ok(data) => data,
+        Err(_) => return,
+    };
+    if let Err(err) = write_to_file(&path, &data) {
+        info!("写入缓存失败 {}: {}", path, err);
+    }
+}
+
+fn cached_probe<T, F, E>(name: &str, max_age_secs: u64, probe: F, to_error: E) -> Result<T, HardwareError>
+where
+    T: Serialize + DeserializeOwned,
+    F: Fn() -> Result<T, Box<dyn Error>>,
+    E: Fn(String) -> HardwareError,
+{
+    if let Some(data) = read_cache(name, max_age_secs) {
+        return Ok(data);
+    }
 
-    data.to_string()
+    let data = probe().map_err(|e| to_error(e.to_string()))?;
+    write_cache(name, &data);
+    Ok(data)
 }
 
-pub async fn info() -> String {
+pub async fn info() -> HardwareInfo {
 
     let info = os_info::get();
     
@@ -228,46 +521,172 @@ pub async fn info() -> String {
         bitness: info.bitness().to_string(),
     };
 
-    let cpu_info = match get_cpu_info().await {
+    let mut errors: Vec<ProbeError> = vec![];
+
+    let mut cpu_info = probe_cpu(&mut errors).await;
+    let system_info = probe_system(&mut errors);
+    let (gpu_info, gpu_topology) = probe_gpu(&mut errors).await;
+
+    let fingerprint = fingerprint::fingerprint_with_gpus(&gpu_info).await;
+    if cpu_info.uuid.is_empty() {
+        cpu_info.uuid = fingerprint.fingerprint.clone();
+    }
+
+    let (mem_info, memory_modules) = probe_memory(&mut errors);
+    let numa = probe_numa(&mut errors);
+    let (disks_info, ignored_mounts, physical_disks) = probe_disks(&mut errors);
+
+    HardwareInfo {
+        os_info,
+        cpu_info,
+        system_info,
+        gpu_info,
+        fingerprint,
+        gpu_topology,
+        mem_info,
+        memory_modules,
+        numa,
+        disks_info,
+        ignored_mounts,
+        physical_disks,
+        errors,
+    }
+}
+
+/// 重新探测缓存中失败的探测项，其余部分沿用缓存，有探测项恢复时返回 true
+async fn retry_failed(hardware: &mut HardwareInfo) -> bool {
+    let previous = hardware.errors.len();
+    let failed: Vec<String> = hardware.errors.drain(..).map(|err| err.probe).collect();
+    let failed = |probe: &str| failed.iter().any(|name| name == probe);
+    let mut errors: Vec<ProbeError> = vec![];
+
+    if failed("cpu") {
+        hardware.cpu_info = probe_cpu(&mut errors).await;
+        if hardware.cpu_info.uuid.is_empty() {
+            hardware.cpu_info.uuid = hardware.fingerprint.fingerprint.clone();
+        }
+    }
+    if failed("system") {
+        hardware.system_info = probe_system(&mut errors);
+    }
+    if failed("gpu") {
+        (hardware.gpu_info, hardware.gpu_topology) = probe_gpu(&mut errors).await;
+        hardware.fingerprint = fingerprint::fingerprint_with_gpus(&hardware.gpu_info).await;
+    }
+    if failed("memory") {
+        (hardware.mem_info, hardware.memory_modules) = probe_memory(&mut errors);
+    }
+    if failed("numa") {
+        hardware.numa = probe_numa(&mut errors);
+    }
+    if failed("disk") {
+        (hardware.disks_info, hardware.ignored_mounts, hardware.physical_disks) = probe_disks(&mut errors);
+    }
+
+    let recovered = errors.len() < previous;
+    hardware.errors = errors;
+    recovered
+}
+
+async fn probe_cpu(errors: &mut Vec<ProbeError>) -> CpuInfo {
+    match get_cpu_info().await {
         Ok(cpu_info) => cpu_info,
-        Err(_) => {
+        Err(err) => {
             info!("获取CPU信息失败");
-            CpuInfo {
-                uuid: "".to_string(),
-                name: "".to_string(),
-                num: 0,
-                speed: 0,
-                core_num: 0
-            }
+            errors.push(ProbeError::from(&HardwareError::Cpu(err.to_string())));
+            CpuInfo::default()
         },
+    }
+}
+
+fn probe_system(errors: &mut Vec<ProbeError>) -> SystemInfo {
+    match get_system_info() {
+        Ok(system_info) => system_info,
+        Err(err) => {
+            info!("获取整机信息失败");
+            errors.push(ProbeError::from(&HardwareError::System(err.to_string())));
+            SystemInfo::default()
+        }
+    }
+}
+
+async fn probe_gpu(errors: &mut Vec<ProbeError>) -> (Vec<GpuInfo>, Option<GpuTopology>) {
+    let gpu_info = match get_gpu_info().await {
+        Ok(gpu_info) => gpu_info,
+        Err(err) => {
+            info!("获取显卡信息失败");
+            errors.push(ProbeError::from(&HardwareError::Gpu(err.to_string())));
+            vec![]
+        }
     };
-    
-    let gpu_info = get_gpu_info().await.unwrap();
-    
+
+    let gpu_topology = if gpu_info.iter().any(|gpu| gpu.vendor == "nvidia") {
+        match blocking(gpu::gpu_topology).await {
+            Ok(gpu_topology) => gpu_topology,
+            Err(err) => {
+                info!("获取显卡拓扑失败");
+                errors.push(ProbeError::from(&HardwareError::Gpu(err.to_string())));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    (gpu_info, gpu_topology)
+}
+
+fn probe_memory(errors: &mut Vec<ProbeError>) -> (MemoryInfo, Vec<MemoryModule>) {
     let mem_info = match get_mem_info() {
         Ok(mem_info) => mem_info,
-        Err(_) => {
+        Err(err) => {
             info!("获取内存信息失败");
-            MemoryInfo {
-                total: 0,
-                free: 0,
-                buffers: 0,
-                cached: 0,
-            }
+            errors.push(ProbeError::from(&HardwareError::Memory(err.to_string())));
+            MemoryInfo::default()
         },
-    }; 
-    let disks_info = get_disk_info().unwrap();
-    let hardware_info = HardwareInfo {
-        os_info,
-        cpu_info,
-        gpu_info,
-        mem_info,
-        disks_info
     };
 
-    let hardware_info_json = serde_json::to_string(&hardware_info).unwrap();
+    let memory_modules = match get_memory_modules() {
+        Ok(memory_modules) => memory_modules,
+        Err(err) => {
+            info!("获取内存条信息失败");
+            errors.push(ProbeError::from(&HardwareError::Memory(err.to_string())));
+            vec![]
+        }
+    };
+
+    (mem_info, memory_modules)
+}
+
+fn probe_numa(errors: &mut Vec<ProbeError>) -> Vec<NumaNode> {
+    match NumaSysfs::default().nodes() {
+        Ok(numa) => numa,
+        Err(err) => {
+            info!("获取 NUMA 信息失败");
+            errors.push(ProbeError::from(&HardwareError::Numa(err.to_string())));
+            vec![]
+        }
+    }
+}
+
+/// 返回真实挂载点、被过滤的挂载点和物理磁盘
+fn probe_disks(errors: &mut Vec<ProbeError>) -> (Vec<DiskInfo>, Vec<DiskInfo>, Vec<PhysicalDisk>) {
+    let mut physical_disks = get_physical_disks();
+
+    let disks = match get_disk_info() {
+        Ok(disks) => disks,
+        Err(err) => {
+            info!("获取磁盘信息失败");
+            errors.push(ProbeError::from(&HardwareError::Disk(err.to_string())));
+            vec![]
+        }
+    };
+    let (disks_info, ignored_mounts): (Vec<DiskInfo>, Vec<DiskInfo>) = disks
+        .into_iter()
+        .partition(|disk| disk.mount_kind != disk::MOUNT_VIRTUAL && disk.mount_kind != disk::MOUNT_BIND);
+    disk::aggregate_storage(&mut physical_disks, &disks_info);
 
-    hardware_info_json.to_string()
+    (disks_info, ignored_mounts, physical_disks)
 }
 
 #[cfg(target_os = "windows")]
@@ -282,7 +701,7 @@ pub async fn get_cpu_info() -> Result<CpuInfo, Box<dyn Error>> {
         },
     };
 
-    let cpu_serial_str = std::str::from_utf8(&cpu_serial_output.stdout).unwrap();
+    let cpu_serial_str = std::str::from_utf8(&cpu_serial_output.stdout)?;
 
     // 解析并打印CPU序列号
     let cpu_serial = cpu_serial_str
@@ -301,7 +720,7 @@ pub async fn get_cpu_info() -> Result<CpuInfo, Box<dyn Error>> {
                 return Err("执行命令失败".into());
             },
         };
-    let output_str = std::str::from_utf8(&output.stdout).unwrap();
+    let output_str = std::str::from_utf8(&output.stdout)?;
     let cpu_model = output_str.lines().find(|&line| !line.contains("Name")).unwrap_or("");
     let name = cpu_model.trim();
 
@@ -314,7 +733,7 @@ pub async fn get_cpu_info() -> Result<CpuInfo, Box<dyn Error>> {
                 return Err("执行命令失败".into());
             },
         };
-    let cpu_sockets_str = std::str::from_utf8(&cpu_sockets_output.stdout).unwrap();
+    let cpu_sockets_str = std::str::from_utf8(&cpu_sockets_output.stdout)?;
     let num = cpu_sockets_str
         .lines()
         .filter(|line| line.trim().len() > 0 && !line.contains("SocketDesignation"))
@@ -335,223 +754,87 @@ pub async fn get_cpu_info() -> Result<CpuInfo, Box<dyn Error>> {
         name: name.to_string(),
         num: num as u32,
         speed,
-        core_num
+        core_num,
+        sockets: num as u32,
+        max_mhz: (speed > 0).then_some(speed as u32),
+        ..Default::default()
     })
 }
 
 #[cfg(not(target_os = "windows"))]
 pub async fn get_cpu_info() -> Result<CpuInfo, Box<dyn Error>> {
-    let output = match std::process::Command::new("sh")
-        .arg("-c")
-        .arg("LC_ALL=C lscpu")
-        .output() {
-            Ok(output) => output,
-            Err(_) => {
-                info!("lscpu 执行失败");
-                return Err("执行命令失败".into());
-            },
-        };
-
-    let output_str = std::str::from_utf8(&output.stdout).unwrap();
-
-    let uuid = "".to_string();
-    let mut name = "".to_string();
-    let mut num = 0;
-    let mut speed = 0;
-    let mut core_num = 0;
-
-    for line in output_str.lines() {
-        if line.starts_with("CPU(s):") {
-            num = line.split(':').nth(1).unwrap().trim().parse::<u32>().unwrap();
-        } else if line.starts_with("Model name:") {
-            name = line.split(':').nth(1).unwrap().trim().to_string();
-        } else if line.starts_with("CPU MHz:") {
-            speed = (line.split(':').nth(1).unwrap().trim().parse::<f64>().unwrap() * 1000.0) as u64;
-        } else if line.starts_with("Core(s) per socket:") {
-            core_num = line.split(':').nth(1).unwrap().trim().parse::<u32>().unwrap();
-        }
-    }
-
-    Ok(CpuInfo {
-        uuid,
-        name,
-        num,
-        speed,
-        core_num
-    })
-
-}
-
-pub async fn get_gpu_info() -> Result<Vec<GpuInfo>, Box<dyn Error>> {
-    // 需要先区分是N卡还是A卡，还是国产显卡，再使用不同的命令来获取信息
-
-    info!("获取显卡信息");
-
-    #[cfg(target_os = "windows")]
-    let gpu_info_output = match std::process::Command::new("cmd")
-        .args(&["/C", "wmic path win32_videocontroller get name"])
-        .output() {
-            Ok(output) => output,
-            Err(_) => {
-                info!("wmic path win32_videocontroller get name 执行失败");
-                return Err("执行命令失败".into());
-            },
-        };
-
-    #[cfg(not(target_os = "windows"))]
-    let gpu_info_output = match std::process::Command::new("lspci")
-        .output() {
-            Ok(output) => output,
-            Err(_) => {
-                info!("lspci 执行失败");
-                return Err("执行命令失败".into());
-            },
-        };
-
-    info!("获取显卡信息成功");
-
-    let gpu_info_str = std::str::from_utf8(&gpu_info_output.stdout).unwrap();
-
-    info!("显卡信息: {}", gpu_info_str);
-
-    // 解析并打印显卡信息
-    for line in gpu_info_str.lines() {
-        let name = line.trim();
-        if name.is_empty() {
-            continue;
-        }
-
-        println!("显卡: {}", name);
-
-        // 根据显卡名称区分不同品牌
-        if name.contains("NVIDIA") {
-            match nvidia().await {
-                Ok(gpu_info) => {
-                    return Ok(gpu_info);
-                },
-                Err(_) => {
-                    info!("获取NVIDIA显卡信息失败");
-                },
+    blocking(|| {
+        // 插槽、核心数等拓扑信息直接读 /proc/cpuinfo 和 sysfs，lscpu 的 CPU(s) 是逻辑线程数
+        let mut cpu_info = CpuSysfs::default().cpu_info()?;
+
+        // 没有 cpufreq 时退回 lscpu 的 CPU max MHz / CPU min MHz，lscpu 不可用时不影响结果
+        if cpu_info.max_mhz.is_none() || cpu_info.min_mhz.is_none() || cpu_info.cur_mhz.is_none() {
+            match std::process::Command::new("sh").arg("-c").arg("LC_ALL=C lscpu").output() {
+                Ok(output) if output.status.success() => {
+                    lscpu_frequencies(&mut cpu_info, &String::from_utf8_lossy(&output.stdout));
+                }
+                _ => info!("lscpu 执行失败，跳过频率补充"),
             }
-
-            return Ok(nvidia_lspci().await?);
-        } else if name.contains("AMD") {
-            // println!("AMD显卡: {}", name);
-        } else if name.contains("华为") {
-            // println!("华为显卡: {}", name);
-        } else {
-            // println!("其他显卡: {}", name);
         }
-    }
-
-    Ok(vec![])
+        cpu_info.speed = cpu::legacy_speed(cpu_info.max_mhz.or(cpu_info.cur_mhz));
 
-    // Ok(vec![
-    //     GpuInfo {
-    //         index: "".to_string(),
-    //         name: "".to_string(),
-    //         uuid: "".to_string(),
-    //         gpu_bus_id: "".to_string(),
-    //         memory_used: "".to_string(),
-    //         memory_total: "".to_string(),
-    //         temperature: "".to_string(),
-    //         power_draw: "".to_string(),
-    //     }
-    // ])
+        Ok(cpu_info)
+    }).await
 }
 
-async fn nvidia_lspci() -> Result<Vec<GpuInfo>, Box<dyn Error>> {
-    let output = std::process::Command::new("sh")
-        .arg("-c")
-        .arg("lspci | grep NVIDIA")
-        .output()
-        .expect("failed to execute process");
-
-    let output_str = String::from_utf8(output.stdout).unwrap();
-
-    info!("nvidia lspci: {}", output_str);
-
-    let re = regex::Regex::new(r"(?P<bus_id>\d+:\d+\.0) .*?: (?P<manufacturer>NVIDIA Corporation) (?P<device>.*?) (\[(?P<model>.*?)\])? \((?P<rev>.*?)\)").unwrap();
-
-    let mut i = 0;
-    let mut gpu_info: Vec<GpuInfo> = vec![];
-
-    for line in output_str.lines() {
-        if let Some(cap) = re.captures(line) {
-            let name = if &cap["model"] != "" {
-                cap["model"].to_string()
-            } else {
-                cap["device"].to_string()
-            };
-        
-            gpu_info.push(GpuInfo {
-                index: i.to_string(),
-                name: name,
-                uuid: "".to_string(),
-                gpu_bus_id: format!("0000:{}", &cap["bus_id"].replace(".0", "")),
-                memory_used: "".to_string(),
-                memory_total: "".to_string(),
-                temperature: "".to_string(),
-                power_draw: "".to_string(),
-            });
-
-            i += 1;
+/// 用 lscpu 的输出补上 sysfs 中没有的频率
+#[cfg(not(target_os = "windows"))]
+fn lscpu_frequencies(cpu_info: &mut CpuInfo, output: &str) {
+    for line in output.lines() {
+        let mhz = lscpu_value(line).parse::<f64>().ok().map(|mhz| mhz.round() as u32);
+        if line.starts_with("CPU max MHz:") {
+            cpu_info.max_mhz = cpu_info.max_mhz.or(mhz);
+        } else if line.starts_with("CPU min MHz:") {
+            cpu_info.min_mhz = cpu_info.min_mhz.or(mhz);
+        } else if line.starts_with("CPU MHz:") {
+            cpu_info.cur_mhz = cpu_info.cur_mhz.or(mhz);
         }
     }
-
-    Ok(gpu_info)
 }
 
-pub async fn nvidia() -> Result<Vec<GpuInfo>, Box<dyn Error>> {
-    let output = match Command::new("nvidia-smi")
-    .arg("--query-gpu=index,name,uuid,gpu_bus_id,memory.used,memory.total,temperature.gpu,power.draw")
-    .arg("--format=csv,noheader")
-    .output()
-    .await {
-        Ok(output) => output,
-        Err(_) => {
-            return Err("nvidia-smi 执行失败".into());
-        },
-    };
-
-    if output.status.success() {
-        let output = String::from_utf8_lossy(&output.stdout);
+#[cfg(not(target_os = "windows"))]
+fn lscpu_value(line: &str) -> &str {
+    line.split_once(':').map(|(_, value)| value.trim()).unwrap_or("")
+}
 
-        if output.contains("NVIDIA-SMI has failed") {
-            return Err("nvidia-smi 执行失败".into());
-        }
+#[cfg(not(target_os = "windows"))]
+pub fn get_system_info() -> Result<SystemInfo, Box<dyn Error>> {
+    DmiSysfs::default().system_info()
+}
 
-        let gpu_info: Vec<GpuInfo> = split_gpu_info(&output)
-            .into_iter()
-            .map(|info| GpuInfo {
-                index: info[0].clone(),
-                name: info[1].clone(),
-                uuid: info[2].clone(),
-                gpu_bus_id: info[3].clone(),
-                memory_used: info[4].clone(),
-                memory_total: info[5].clone(),
-                temperature: info[6].clone(),
-                power_draw: info[7].clone(),
-            })
-            .collect();
+/// Windows 上暂未实现，返回空的整机信息
+#[cfg(target_os = "windows")]
+pub fn get_system_info() -> Result<SystemInfo, Box<dyn Error>> {
+    Ok(SystemInfo::default())
+}
 
-        return Ok(gpu_info);
-    }
+pub async fn get_gpu_info() -> Result<Vec<GpuInfo>, Box<dyn Error>> {
+    // 需要先区分是N卡还是A卡，还是国产显卡，再使用不同的后端来获取信息
+    info!("获取显卡信息");
 
-    Err("nvidia-smi 执行失败".into())
+    // 共用一个 registry，Intel 等后端需要保留上一次的读数
+    let registry = GpuRegistry::shared();
+    blocking(move || registry.enumerate()).await
 }
 
-fn split_gpu_info(gpu_info: &str) -> Vec<Vec<String>> {
-    gpu_info
-        .lines()
-        .map(|line| {
-            line.split(',')
-                .map(|s| s.trim().to_string())
-                .collect()
-        })
-        .collect()
+/// 在阻塞线程池中执行同步的探测函数，nvidia-smi、npu-smi 等命令不会占用运行时的工作线程
+pub(crate) async fn blocking<T, F>(probe: F) -> Result<T, Box<dyn Error>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Box<dyn Error>> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(move || probe().map_err(|e| e.to_string())).await {
+        Ok(result) => result.map_err(|e| e.into()),
+        Err(err) => Err(err.into()),
+    }
 }
 
+/// 打开所有 N 卡的持久模式，只操作部分显卡或需要回滚时使用 gpu::GpuControl
 pub async fn enable_gpu_persistence_mode() -> Result<(), Box<dyn Error>> {
     let output = Command::new("nvidia-smi")
         .arg("-pm")
@@ -589,54 +872,100 @@ pub fn get_mem_info() -> Result<MemoryInfo, Box<dyn Error>> {
     Ok(MemoryInfo {
         total,
         free,
-        buffers: 0,
-        cached: 0,
+        ..Default::default()
     })
 }
 
+/// 虚拟机里通常没有 SMBIOS 表，普通用户没有权限读取，这两种情况都返回空列表
 #[cfg(not(target_os = "windows"))]
-pub fn get_mem_info() -> Result<MemoryInfo, Box<dyn Error>> {
-    let mut sys = sysinfo::System::new_all();
-    
-    // First we update all information of our `System` struct.
-    sys.refresh_all();
+pub fn get_memory_modules() -> Result<Vec<MemoryModule>, Box<dyn Error>> {
+    match smbios::read_memory_modules("/sys/firmware/dmi/tables/DMI") {
+        Err(err) => match err.downcast_ref::<io::Error>().map(|err| err.kind()) {
+            Some(io::ErrorKind::NotFound) => Ok(vec![]),
+            Some(io::ErrorKind::PermissionDenied) => {
+                info!("没有权限读取 SMBIOS 表，跳过内存条信息");
+                Ok(vec![])
+            }
+            _ => Err(err),
+        },
+        result => result,
+    }
+}
 
-    Ok(MemoryInfo {
-        total: sys.total_memory(),
-        free: sys.free_memory(),
-        buffers: 0,
-        cached: 0,
-    })
+/// Windows 上暂未实现
+#[cfg(target_os = "windows")]
+pub fn get_memory_modules() -> Result<Vec<MemoryModule>, Box<dyn Error>> {
+    Ok(vec![])
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_mem_info() -> Result<MemoryInfo, Box<dyn Error>> {
+    meminfo::read_meminfo("/proc/meminfo")
 }
 
 #[cfg(not(target_os = "windows"))]
 pub fn get_disk_info() -> Result<Vec<DiskInfo>, Box<dyn std::error::Error>> {
     info!("获取磁盘信息");
-    use sysinfo::{
-        Disks, System,
-    };
+    use sysinfo::{Disks, DiskKind};
 
-    let _ = System::new_all();
+    let block = BlockSysfs::default();
+    let physical_disks = block.physical_disks();
 
     let disks = Disks::new_with_refreshed_list();
     let mut disk_vec: Vec<DiskInfo> = vec![];
 
     for disk in &disks {
+        let device = disk.name().to_string_lossy().into_owned();
+        let physical_disk = block
+            .disk_of(&device)
+            .and_then(|name| physical_disks.iter().find(|physical_disk| physical_disk.name == name));
+
+        let media_type = match (physical_disk, disk.kind()) {
+            (Some(physical_disk), _) => physical_disk.media_type(),
+            (None, DiskKind::HDD) => "HDD",
+            (None, DiskKind::SSD) => "SSD",
+            (None, _) => "Unspecified",
+        };
+
         disk_vec.push(DiskInfo {
-            media_type: format!("{:?}", disk.kind()),
-            name: format!("{:?}", disk.name()),
-            size: format!("{:?}", disk.total_space()),
-            mount_point: format!("{:?}", disk.mount_point()),
-            available_space: format!("{:?}", disk.available_space()),
-            file_system: format!("{:?}", disk.file_system()),
-            total_space: format!("{:?}", disk.total_space()),
-            kind: format!("{:?}", disk.kind()),
+            media_type: media_type.to_string(),
+            name: physical_disk.map(|physical_disk| physical_disk.model.clone()).unwrap_or_default(),
+            size: physical_disk.map(|physical_disk| physical_disk.size_bytes).unwrap_or(0),
+            mount_point: disk.mount_point().to_string_lossy().into_owned(),
+            available_space: disk.available_space(),
+            file_system: disk.file_system().to_string_lossy().into_owned(),
+            total_space: disk.total_space(),
+            kind: physical_disk.map(|physical_disk| physical_disk.transport.clone()).unwrap_or_default(),
+            disk: physical_disk.map(|physical_disk| physical_disk.name.clone()).unwrap_or_default(),
+            device,
+            ..Default::default()
         });
     }
 
+    // 读不到 mountinfo 时只按文件系统类型过滤
+    let mounts = match std::fs::read_to_string("/proc/self/mountinfo") {
+        Ok(data) => disk::parse_mountinfo(&data),
+        Err(_) => {
+            info!("读取 /proc/self/mountinfo 失败");
+            vec![]
+        }
+    };
+    disk::classify_mounts(&mut disk_vec, &mounts, &DiskConfig::load());
+
     Ok(disk_vec)
 }
 
+#[cfg(not(target_os = "windows"))]
+pub fn get_physical_disks() -> Vec<PhysicalDisk> {
+    BlockSysfs::default().physical_disks()
+}
+
+/// Windows 上 get_disk_info 返回的已经是物理磁盘
+#[cfg(target_os = "windows")]
+pub fn get_physical_disks() -> Vec<PhysicalDisk> {
+    vec![]
+}
+
 #[cfg(target_os = "windows")]
 pub fn get_disk_info() -> Result<Vec<DiskInfo>, Box<dyn std::error::Error>> {
     info!("获取磁盘信息");
@@ -657,7 +986,7 @@ pub fn get_disk_info() -> Result<Vec<DiskInfo>, Box<dyn std::error::Error>> {
             }
         };
 
-    let data = std::str::from_utf8(&output.stdout).unwrap();
+    let data = std::str::from_utf8(&output.stdout)?;
 
     // 如果只有一个结果会出错
     let data = if data.starts_with('{') {
@@ -675,21 +1004,23 @@ pub fn get_disk_info() -> Result<Vec<DiskInfo>, Box<dyn std::error::Error>> {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn get_net_info() -> Result<String, Box<dyn std::error::Error>> {
+pub fn get_net_info() -> Result<Vec<NetInfo>, Box<dyn std::error::Error>> {
     use std::process::Command;
     let output = Command::new("ip")
         .arg("-j")
         .arg("a")
-        .output()
-        .expect("Failed to execute command");
+        .output()?;
 
-    let output_str = String::from_utf8(output.stdout).unwrap();
-    let interfaces: Value = serde_json::from_str(&output_str).unwrap();
+    let output_str = String::from_utf8(output.stdout)?;
+    let interfaces: Value = serde_json::from_str(&output_str)?;
 
     let mut net_info = vec![];
 
-    for interface in interfaces.as_array().unwrap() {
-        let name = interface["ifname"].as_str().unwrap();
+    for interface in interfaces.as_array().ok_or("ip -j a 输出不是数组")? {
+        let name = match interface["ifname"].as_str() {
+            Some(name) => name,
+            None => continue
+        };
         let mac = match interface["address"].as_str() {
             Some(mac) => mac,
             None => ""
@@ -698,26 +1029,23 @@ pub fn get_net_info() -> Result<String, Box<dyn std::error::Error>> {
             Some(ip) => ip,
             None => ""
         };
-        let status = interface["operstate"].as_str().unwrap();
-
-        net_info.push(json!({
-            "name": name,
-            "status": status,
-            "mac": mac,
-            "ip": ip,
-            "received": 0,
-            "sent": 0
-        }));
+        let status = interface["operstate"].as_str().unwrap_or("UNKNOWN");
+
+        net_info.push(NetInfo {
+            name: name.to_string(),
+            status: status.to_string(),
+            mac: mac.to_string(),
+            ip: Value::String(ip.to_string()),
+            received: 0,
+            sent: 0
+        });
     }
 
-    // net_info 转为 json
-    let net_info_json = serde_json::to_string(&net_info).unwrap();
-
-    Ok(net_info_json)
+    Ok(net_info)
 }
 
 #[cfg(target_os = "windows")]
-pub fn get_net_info() -> Result<String, Box<dyn std::error::Error>> {
+pub fn get_net_info() -> Result<Vec<NetInfo>, Box<dyn std::error::Error>> {
     let output = match std::process::Command::new("powershell")
     .args(&[
         "Get-NetAdapter | Where-Object { $_.Status -eq 'Up' } | ForEach-Object {
@@ -737,19 +1065,28 @@ pub fn get_net_info() -> Result<String, Box<dyn std::error::Error>> {
     ])
     .output() {
         Ok(data) => data,
-        Err(_) => return Ok("".to_string())
+        Err(_) => return Err("powershell 执行失败".into())
     };
 
-    let data = match std::str::from_utf8(&output.stdout) {
-        Ok(data) => data.to_string(),
-        Err(_) => "".to_string()
-    };
+    let data = std::str::from_utf8(&output.stdout)?.trim();
 
-    if data.starts_with('{') {
-        Ok(format!("[{}]", data))
+    // 只有一个网卡时输出的是对象而不是数组
+    let data = if data.starts_with('{') {
+        format!("[{}]", data)
     } else {
-        Ok(data)
-    }
+        data.to_string()
+    };
+
+    let adapters: Vec<Value> = serde_json::from_str(&data)?;
+
+    Ok(adapters.iter().map(|adapter| NetInfo {
+        name: adapter["name"].as_str().unwrap_or("").to_string(),
+        status: adapter["status"].as_str().unwrap_or("").to_string(),
+        mac: adapter["mac"].as_str().unwrap_or("").to_string(),
+        ip: adapter["ip"].clone(),
+        received: adapter["received"].as_u64().unwrap_or(0),
+        sent: adapter["sent"].as_u64().unwrap_or(0),
+    }).collect())
 }
 
 
@@ -758,20 +1095,19 @@ use std::path::Path;
 use std::io;
 use std::time::SystemTime;
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FileInfo {
-    path: String,
-    size: u64,
-    creation_time: u64,
+    pub path: String,
+    pub size: u64,
+    pub creation_time: u64,
 }
 
-pub fn get_file_info(path: String) -> String {
+pub fn get_file_info(path: String) -> Result<Vec<FileInfo>, Box<dyn Error>> {
     let path = Path::new(&path);
     let mut files_info = Vec::new();
 
-    visit_dirs(path, &mut files_info).unwrap();
-    let json = serde_json::to_string_pretty(&files_info).unwrap();
-    json
+    visit_dirs(path, &mut files_info)?;
+    Ok(files_info)
 }
 
 pub fn visit_dirs(dir: &Path, files_info: &mut Vec<FileInfo>) -> io::Result<()> {
@@ -782,7 +1118,7 @@ pub fn visit_dirs(dir: &Path, files_info: &mut Vec<FileInfo>) -> io::Result<()>
             if path.is_dir() {
                 visit_dirs(&path, files_info)?;
             } else {
-                let info = file_info(&entry);
+                let info = file_info(&entry)?;
                 files_info.push(info);
             }
         }
@@ -790,22 +1126,22 @@ pub fn visit_dirs(dir: &Path, files_info: &mut Vec<FileInfo>) -> io::Result<()>
     Ok(())
 }
 
-pub fn file_info(entry: &DirEntry) -> FileInfo {
+pub fn file_info(entry: &DirEntry) -> io::Result<FileInfo> {
     let path = entry.path();
-    let metadata = entry.metadata().unwrap_or_else(|_| panic!("无法获取元数据"));
+    let metadata = entry.metadata()?;
 
     let file_size = metadata.len();
     let creation_time = metadata.created()
         .unwrap_or(SystemTime::UNIX_EPOCH)
         .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
 
-    FileInfo {
+    Ok(FileInfo {
         path: path.to_string_lossy().into_owned(),
         size: file_size,
         creation_time: creation_time,
-    }
+    })
 }
 
 
@@ -25,8 +25,18 @@ async fn main() {
         std::process::exit(1);
     };
 
+    // 每 5 秒采样一次显卡，统计窗口与上报间隔一致
+    wei_hardware::start_gpu_sampler(tokio::time::Duration::from_secs(5), tokio::time::Duration::from_secs(60));
+
     loop {
-        let config_data: serde_json::Value = serde_json::from_str(&wei_hardware::all().await).unwrap();
+        let config_data = match serde_json::to_value(wei_hardware::all().await) {
+            Ok(data) => data,
+            Err(e) => {
+                info!("hardware report error: {}", e);
+                tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+                continue;
+            }
+        };
         let client = match reqwest::Client::builder()
         .timeout(tokio::time::Duration::from_secs(30))
         .build() {
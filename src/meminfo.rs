@@ -0,0 +1,196 @@
+//! 解析 /proc/meminfo，替代 sysinfo 的整机扫描
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use crate::MemoryInfo;
+
+/// 读取并解析 meminfo 文件
+pub fn read_meminfo<P: AsRef<Path>>(path: P) -> Result<MemoryInfo, Box<dyn Error>> {
+    let data = std::fs::read_to_string(path)?;
+    let mem_info = parse_meminfo(&data);
+    if mem_info.total == 0 {
+        return Err("meminfo 中没有 MemTotal".into());
+    }
+    Ok(mem_info)
+}
+
+/// 解析 /proc/meminfo，带 kB 的值换算成字节，HugePages_* 是页数
+pub fn parse_meminfo(data: &str) -> MemoryInfo {
+    let values: HashMap<&str, u64> = data
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let mut parts = value.split_whitespace();
+            let number = parts.next()?.parse::<u64>().ok()?;
+            let number = match parts.next() {
+                Some("kB") => number * 1024,
+                _ => number,
+            };
+            Some((key.trim(), number))
+        })
+        .collect();
+    let value = |key: &str| values.get(key).copied();
+
+    MemoryInfo {
+        total: value("MemTotal").unwrap_or(0),
+        free: value("MemFree").unwrap_or(0),
+        buffers: value("Buffers").unwrap_or(0),
+        cached: value("Cached").unwrap_or(0),
+        available_bytes: value("MemAvailable"),
+        shared_bytes: value("Shmem"),
+        swap_total_bytes: value("SwapTotal"),
+        swap_free_bytes: value("SwapFree"),
+        hugepages_total: value("HugePages_Total"),
+        hugepages_free: value("HugePages_Free"),
+        hugepage_size_bytes: value("Hugepagesize"),
+        committed_bytes: value("Committed_AS"),
+        commit_limit_bytes: value("CommitLimit"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // x86 服务器上完整的 /proc/meminfo
+    const X86: &str = "\
+MemTotal:       527951092 kB
+MemFree:        401234560 kB
+MemAvailable:   498765432 kB
+Buffers:          2345678 kB
+Cached:          91234567 kB
+SwapCached:             0 kB
+Active:          45678901 kB
+Inactive:        67890123 kB
+Active(anon):    23456789 kB
+Inactive(anon):    123456 kB
+Active(file):    22222112 kB
+Inactive(file):  67766667 kB
+Unevictable:        12345 kB
+Mlocked:            12345 kB
+SwapTotal:        8388604 kB
+SwapFree:         8388604 kB
+Dirty:               1234 kB
+Writeback:              0 kB
+AnonPages:       23500000 kB
+Mapped:           3456789 kB
+Shmem:            1234567 kB
+KReclaimable:     4567890 kB
+Slab:             7890123 kB
+SReclaimable:     4567890 kB
+SUnreclaim:       3322233 kB
+KernelStack:        98765 kB
+PageTables:        123456 kB
+NFS_Unstable:           0 kB
+Bounce:                 0 kB
+WritebackTmp:           0 kB
+CommitLimit:    272364148 kB
+Committed_AS:    45678901 kB
+VmallocTotal:   34359738367 kB
+VmallocUsed:       456789 kB
+VmallocChunk:           0 kB
+Percpu:            234560 kB
+HardwareCorrupted:      0 kB
+AnonHugePages:   12345678 kB
+ShmemHugePages:         0 kB
+ShmemPmdMapped:         0 kB
+FileHugePages:          0 kB
+FilePmdMapped:          0 kB
+HugePages_Total:        0
+HugePages_Free:         0
+HugePages_Rsvd:         0
+HugePages_Surp:         0
+Hugepagesize:        2048 kB
+Hugetlb:                0 kB
+DirectMap4k:      1234567 kB
+DirectMap2M:    123456789 kB
+DirectMap1G:    414187520 kB
+";
+
+    // 3.14 之前的内核（如 CentOS 6）没有 MemAvailable
+    const OLD_KERNEL: &str = "\
+MemTotal:        8061404 kB
+MemFree:          523412 kB
+Buffers:          301248 kB
+Cached:          5212360 kB
+SwapCached:         1024 kB
+SwapTotal:       4194300 kB
+SwapFree:        4190204 kB
+Shmem:             12412 kB
+CommitLimit:     8224000 kB
+Committed_AS:    2345678 kB
+HugePages_Total:       0
+HugePages_Free:        0
+HugePages_Rsvd:        0
+HugePages_Surp:        0
+Hugepagesize:       2048 kB
+";
+
+    // 预留了 1G 大页的 DPDK / 推理节点
+    const HUGEPAGES: &str = "\
+MemTotal:       263856128 kB
+MemFree:         12345678 kB
+MemAvailable:    20000000 kB
+Buffers:            12345 kB
+Cached:           6543210 kB
+Shmem:             123456 kB
+SwapTotal:              0 kB
+SwapFree:               0 kB
+HugePages_Total:      192
+HugePages_Free:        64
+HugePages_Rsvd:         0
+HugePages_Surp:         0
+Hugepagesize:     1048576 kB
+Hugetlb:        201326592 kB
+";
+
+    #[test]
+    fn parses_full_x86_meminfo() {
+        let mem = parse_meminfo(X86);
+        assert_eq!(mem.total, 527951092 * 1024);
+        assert_eq!(mem.free, 401234560 * 1024);
+        assert_eq!(mem.buffers, 2345678 * 1024);
+        assert_eq!(mem.cached, 91234567 * 1024);
+        assert_eq!(mem.available_bytes, Some(498765432 * 1024));
+        assert_eq!(mem.shared_bytes, Some(1234567 * 1024));
+        assert_eq!(mem.swap_total_bytes, Some(8388604 * 1024));
+        assert_eq!(mem.swap_free_bytes, Some(8388604 * 1024));
+        assert_eq!(mem.committed_bytes, Some(45678901 * 1024));
+        assert_eq!(mem.commit_limit_bytes, Some(272364148 * 1024));
+        assert_eq!(mem.hugepages_total, Some(0));
+        assert_eq!(mem.hugepage_size_bytes, Some(2 << 20));
+    }
+
+    #[test]
+    fn available_is_none_on_old_kernels() {
+        let mem = parse_meminfo(OLD_KERNEL);
+        assert_eq!(mem.total, 8061404 * 1024);
+        assert_eq!(mem.available_bytes, None);
+        assert_eq!(mem.swap_free_bytes, Some(4190204 * 1024));
+    }
+
+    #[test]
+    fn hugepages_are_page_counts() {
+        let mem = parse_meminfo(HUGEPAGES);
+        // HugePages_* 没有单位，不乘 1024
+        assert_eq!(mem.hugepages_total, Some(192));
+        assert_eq!(mem.hugepages_free, Some(64));
+        assert_eq!(mem.hugepage_size_bytes, Some(1 << 30));
+        // 页数乘页大小等于 Hugetlb
+        let hugetlb_bytes = 201326592u64 * 1024;
+        assert_eq!(mem.hugepages_total.unwrap() * mem.hugepage_size_bytes.unwrap(), hugetlb_bytes);
+    }
+
+    #[test]
+    fn read_requires_mem_total() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("meminfo");
+        std::fs::write(&path, "MemFree:  1024 kB\n").unwrap();
+        assert!(read_meminfo(&path).is_err());
+        std::fs::write(&path, OLD_KERNEL).unwrap();
+        assert_eq!(read_meminfo(&path).unwrap().total, 8061404 * 1024);
+        assert!(read_meminfo(dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! 从 /sys/devices/system/node 读取 NUMA 拓扑，以及挂在各个节点上的显卡、网卡和 NVMe
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::pci::{self, PciAddress, PciDevice, PciIds};
+use crate::sysfs;
+
+/// 挂在某个 NUMA 节点上的 PCI 设备
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NumaDevice {
+    pub address: PciAddress,
+    /// 网卡为接口名，NVMe 为控制器名（如 nvme0），显卡为型号
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct NumaNode {
+    pub node: u32,
+    /// 原始的 cpulist，如 "0-31,64-95"
+    pub cpu_list: String,
+    pub cpus: Vec<u32>,
+    /// 单位字节
+    pub mem_total_bytes: Option<u64>,
+    pub mem_free_bytes: Option<u64>,
+    /// 只包括显卡后端支持的设备，不含 BMC 的显示芯片
+    pub gpus: Vec<NumaDevice>,
+    pub nics: Vec<NumaDevice>,
+    pub nvme: Vec<NumaDevice>,
+}
+
+/// 通过 sysfs 获取 NUMA 节点
+pub struct NumaSysfs {
+    node_root: PathBuf,
+    pci_root: PathBuf,
+}
+
+impl Default for NumaSysfs {
+    fn default() -> Self {
+        NumaSysfs::with_root("/sys/devices/system/node", "/sys/bus/pci/devices")
+    }
+}
+
+impl NumaSysfs {
+    /// 指定 node 目录和 PCI 设备目录，测试时可以指向伪造的目录树
+    pub fn with_root<N: Into<PathBuf>, P: Into<PathBuf>>(node_root: N, pci_root: P) -> Self {
+        NumaSysfs {
+            node_root: node_root.into(),
+            pci_root: pci_root.into(),
+        }
+    }
+
+    /// 内核没有开启 NUMA 时没有 node 目录，返回空列表
+    pub fn nodes(&self) -> Result<Vec<NumaNode>, Box<dyn Error>> {
+        match std::fs::read_dir(&self.node_root) {
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        }
+
+        let mut nodes: Vec<NumaNode> = sysfs::numbered_entries(&self.node_root, "node")
+            .into_iter()
+            .map(|(node, dir)| {
+                let cpu_list = sysfs::read_string(dir.join("cpulist")).unwrap_or_default();
+                let meminfo = std::fs::read_to_string(dir.join("meminfo")).unwrap_or_default();
+                NumaNode {
+                    node,
+                    cpus: sysfs::parse_cpu_list(&cpu_list),
+                    cpu_list,
+                    mem_total_bytes: node_meminfo(&meminfo, "MemTotal"),
+                    mem_free_bytes: node_meminfo(&meminfo, "MemFree"),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        // 单节点机器上设备的 numa_node 通常是 -1，全部归到唯一的节点上
+        let single = nodes.len() == 1;
+        for device in pci::scan_root(&self.pci_root, PciIds::system()) {
+            let node = match device.numa_node {
+                Some(node) => nodes.iter_mut().find(|n| n.node == node),
+                None if single => nodes.first_mut(),
+                None => None,
+            };
+            let node = match node {
+                Some(node) => node,
+                None => continue,
+            };
+
+            let dir = self.pci_root.join(device.address.to_string());
+            if crate::gpu::is_supported_gpu(&device) {
+                node.gpus.push(NumaDevice { address: device.address, name: device.model() });
+            } else if device.is_network() {
+                node.nics.extend(child_names(&dir, "net").into_iter().map(|name| NumaDevice { address: device.address, name }));
+            } else if device.is_nvme() {
+                node.nvme.push(nvme_device(&dir, &device));
+            }
+        }
+
+        Ok(nodes)
+    }
+}
+
+/// 节点 meminfo 中的一项，格式为 "Node 0 MemTotal:       65843200 kB"
+fn node_meminfo(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.split_whitespace().last()? != key {
+            return None;
+        }
+        let mut parts = value.split_whitespace();
+        let number = parts.next()?.parse::<u64>().ok()?;
+        match parts.next() {
+            Some("kB") => Some(number * 1024),
+            _ => Some(number),
+        }
+    })
+}
+
+/// PCI 设备目录下某个子目录中的条目名，如 net/eth0、nvme/nvme0
+fn child_names(dir: &Path, child: &str) -> Vec<String> {
+    let mut names: Vec<String> = match std::fs::read_dir(dir.join(child)) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => vec![],
+    };
+    names.sort();
+    names
+}
+
+fn nvme_device(dir: &Path, device: &PciDevice) -> NumaDevice {
+    let name = child_names(dir, "nvme").into_iter().next().unwrap_or_else(|| device.model());
+    NumaDevice { address: device.address, name }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(path: &Path, data: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    fn pci_device(root: &Path, address: &str, vendor: &str, device: &str, class: &str, numa_node: &str) -> PathBuf {
+        let dir = root.join(address);
+        write(&dir.join("vendor"), vendor);
+        write(&dir.join("device"), device);
+        write(&dir.join("class"), class);
+        write(&dir.join("numa_node"), numa_node);
+        dir
+    }
+
+    fn node(root: &Path, node: u32, cpu_list: &str, total_kb: u64, free_kb: u64) {
+        let dir = root.join(format!("node{}", node));
+        write(&dir.join("cpulist"), &format!("{}\n", cpu_list));
+        write(&dir.join("meminfo"), &format!(
+            "Node {node} MemTotal:       {total_kb} kB\nNode {node} MemFree:        {free_kb} kB\nNode {node} MemUsed:        {} kB\n",
+            total_kb - free_kb,
+        ));
+    }
+
+    fn addresses(devices: &[NumaDevice]) -> Vec<String> {
+        devices.iter().map(|device| device.address.to_string()).collect()
+    }
+
+    #[test]
+    fn assigns_devices_to_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_root = dir.path().join("node");
+        let pci_root = dir.path().join("pci");
+        write(&node_root.join("online"), "0-1\n");
+        node(&node_root, 0, "0-31,64-95", 263_856_128, 201_326_592);
+        node(&node_root, 1, "32-63,96-127", 264_241_152, 250_000_000);
+
+        // BMC 的 ASPEED 显示芯片和华为 iBMC 不算显卡
+        pci_device(&pci_root, "0000:03:00.0", "0x1a03\n", "0x2000\n", "0x030000\n", "0\n");
+        pci_device(&pci_root, "0000:04:00.0", "0x19e5\n", "0x1711\n", "0x030000\n", "0\n");
+        pci_device(&pci_root, "0000:17:00.0", "0x10de\n", "0x20b2\n", "0x030200\n", "0\n");
+        pci_device(&pci_root, "0000:c1:00.0", "0x19e5\n", "0xd802\n", "0x120000\n", "1\n");
+        let nic = pci_device(&pci_root, "0000:98:00.0", "0x15b3\n", "0x101d\n", "0x020000\n", "1\n");
+        fs::create_dir_all(nic.join("net/ens5f1")).unwrap();
+        fs::create_dir_all(nic.join("net/ens5f0")).unwrap();
+        let nvme = pci_device(&pci_root, "0000:e3:00.0", "0x144d\n", "0xa80a\n", "0x010802\n", "1\n");
+        fs::create_dir_all(nvme.join("nvme/nvme0")).unwrap();
+        // 没有 NUMA 信息的设备在多节点机器上不归属任何节点
+        pci_device(&pci_root, "0000:65:00.0", "0x10de\n", "0x2330\n", "0x030200\n", "-1\n");
+
+        let nodes = NumaSysfs::with_root(&node_root, &pci_root).nodes().unwrap();
+        assert_eq!(nodes.len(), 2);
+
+        assert_eq!(nodes[0].node, 0);
+        assert_eq!(nodes[0].cpu_list, "0-31,64-95");
+        assert_eq!(nodes[0].cpus.len(), 64);
+        assert_eq!(nodes[0].cpus[32], 64);
+        assert_eq!(nodes[0].mem_total_bytes, Some(263_856_128 * 1024));
+        assert_eq!(nodes[0].mem_free_bytes, Some(201_326_592 * 1024));
+        assert_eq!(addresses(&nodes[0].gpus), vec!["0000:17:00.0"]);
+        assert!(nodes[0].nics.is_empty());
+
+        assert_eq!(addresses(&nodes[1].gpus), vec!["0000:c1:00.0"]);
+        let nics: Vec<&str> = nodes[1].nics.iter().map(|nic| nic.name.as_str()).collect();
+        assert_eq!(nics, vec!["ens5f0", "ens5f1"]);
+        assert_eq!(addresses(&nodes[1].nics), vec!["0000:98:00.0", "0000:98:00.0"]);
+        assert_eq!(nodes[1].nvme.len(), 1);
+        assert_eq!(nodes[1].nvme[0].name, "nvme0");
+    }
+
+    #[test]
+    fn single_node_takes_unassigned_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_root = dir.path().join("node");
+        let pci_root = dir.path().join("pci");
+        node(&node_root, 0, "0-15", 65_843_200, 1_024);
+        pci_device(&pci_root, "0000:01:00.0", "0x10de\n", "0x2684\n", "0x030000\n", "-1\n");
+        pci_device(&pci_root, "0000:00:02.0", "0x8086\n", "0xa780\n", "0x030000\n", "-1\n");
+
+        let nodes = NumaSysfs::with_root(&node_root, &pci_root).nodes().unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(addresses(&nodes[0].gpus), vec!["0000:00:02.0", "0000:01:00.0"]);
+    }
+
+    #[test]
+    fn missing_node_directory_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let nodes = NumaSysfs::with_root(dir.path().join("node"), dir.path().join("pci")).nodes().unwrap();
+        assert!(nodes.is_empty());
+    }
+
+    #[test]
+    fn parses_node_meminfo() {
+        let meminfo = "Node 1 MemTotal:       264241152 kB\nNode 1 MemFree:        250000000 kB\nNode 1 HugePages_Total:     0\n";
+        assert_eq!(node_meminfo(meminfo, "MemTotal"), Some(264241152 * 1024));
+        assert_eq!(node_meminfo(meminfo, "HugePages_Total"), Some(0));
+        assert_eq!(node_meminfo(meminfo, "MemUsed"), None);
+    }
+}
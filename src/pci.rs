@@ -0,0 +1,551 @@
+//! 直接读取 /sys/bus/pci/devices 枚举 PCI 设备，不依赖 lspci
+
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::sysfs;
+
+/// 常见的 pci.ids 安装位置
+const PCI_IDS_PATHS: [&str; 4] = [
+    "/usr/share/hwdata/pci.ids",
+    "/usr/share/misc/pci.ids",
+    "/usr/share/pci.ids",
+    "/usr/local/share/pci.ids",
+];
+
+/// 系统里没有 pci.ids 时使用的精简数据，只包含算力节点上常见的厂商和显卡
+const EMBEDDED_PCI_IDS: &str = "\
+1000  Broadcom / LSI
+1002  Advanced Micro Devices, Inc. [AMD/ATI]
+\t740f  Aldebaran/MI200 [Instinct MI210]
+\t740c  Aldebaran/MI200 [Instinct MI250X/MI250]
+\t74a1  Aqua Vanjaram [Instinct MI300X]
+\t73bf  Navi 21 [Radeon RX 6800/6800 XT / 6900 XT]
+\t744c  Navi 31 [Radeon RX 7900 XT/7900 XTX/7900 GRE/7900M]
+10de  NVIDIA Corporation
+\t1db4  GV100GL [Tesla V100 PCIe 16GB]
+\t1db6  GV100GL [Tesla V100 PCIe 32GB]
+\t1eb8  TU104GL [Tesla T4]
+\t1e04  TU102 [GeForce RTX 2080 Ti]
+\t20b0  GA100 [A100 SXM4 40GB]
+\t20b2  GA100 [A100 SXM4 80GB]
+\t20b5  GA100 [A100 PCIe 80GB]
+\t20f1  GA100 [A100 PCIe 40GB]
+\t2204  GA102 [GeForce RTX 3090]
+\t2208  GA102 [GeForce RTX 3080 Ti]
+\t2230  GA102GL [RTX A6000]
+\t2235  GA102GL [A40]
+\t2236  GA102GL [A10]
+\t2330  GH100 [H100 SXM5 80GB]
+\t2331  GH100 [H100 PCIe]
+\t2684  AD102 [GeForce RTX 4090]
+\t26b9  AD102GL [L40S]
+\t27b8  AD104GL [L4]
+1af4  Red Hat, Inc.
+144d  Samsung Electronics Co Ltd
+15b3  Mellanox Technologies
+19e5  Huawei Technologies Co., Ltd.
+\td801  Ascend 310
+\td802  Ascend 910
+\td500  Ascend 310P
+8086  Intel Corporation
+\t56a0  DG2 [Arc A770]
+\t56a1  DG2 [Arc A750]
+\t56a5  DG2 [Arc A380]
+\t56c0  ATS-M [Data Center GPU Flex 170]
+\t56c1  ATS-M [Data Center GPU Flex 140]
+\t0bd5  Ponte Vecchio XT (2 Tile) [Data Center GPU Max 1550]
+\t0bda  Ponte Vecchio XT (1 Tile) [Data Center GPU Max 1100]
+";
+
+/// PCI 地址，domain:bus:device.function
+///
+/// 可以解析以下几种写法，比较时忽略大小写和 domain 的位数：
+/// - lspci: `3b:00.0`
+/// - sysfs / lspci -D: `0000:3b:00.0`
+/// - nvidia-smi: `00000000:3B:00.0`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PciAddress {
+    pub domain: u32,
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+}
+
+impl PciAddress {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (rest, function) = match value.rsplit_once('.') {
+            Some((rest, function)) => (rest, u8::from_str_radix(function, 16).ok()?),
+            // 旧版本上报的 gpu_bus_id 去掉了 .0
+            None => (value, 0),
+        };
+
+        let parts: Vec<&str> = rest.split(':').collect();
+        let (domain, bus, device) = match parts.as_slice() {
+            [bus, device] => ("0", *bus, *device),
+            [domain, bus, device] => (*domain, *bus, *device),
+            _ => return None,
+        };
+        if domain.len() > 8 || bus.len() > 2 || device.len() > 2 {
+            return None;
+        }
+
+        let address = PciAddress {
+            domain: u32::from_str_radix(domain, 16).ok()?,
+            bus: u8::from_str_radix(bus, 16).ok()?,
+            device: u8::from_str_radix(device, 16).ok()?,
+            function,
+        };
+
+        // device 只有 5 位，function 只有 3 位
+        if address.device > 0x1f || address.function > 7 {
+            return None;
+        }
+        Some(address)
+    }
+
+    /// lspci 的写法，domain 为 0 时省略，如 `3b:00.0`
+    pub fn lspci(&self) -> String {
+        if self.domain == 0 {
+            format!("{:02x}:{:02x}.{:x}", self.bus, self.device, self.function)
+        } else {
+            self.to_string()
+        }
+    }
+
+    /// nvidia-smi 的写法，如 `00000000:3B:00.0`
+    pub fn nvidia_smi(&self) -> String {
+        format!("{:08X}:{:02X}:{:02X}.{:X}", self.domain, self.bus, self.device, self.function)
+    }
+}
+
+/// sysfs 的写法，如 `0000:3b:00.0`
+impl fmt::Display for PciAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:02x}:{:02x}.{:x}", self.domain, self.bus, self.device, self.function)
+    }
+}
+
+impl FromStr for PciAddress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        PciAddress::parse(value).ok_or(format!("无效的 PCI 地址: {}", value))
+    }
+}
+
+impl Serialize for PciAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for PciAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PciDevice {
+    /// Windows 下拿不到总线地址，为 0000:00:00.0
+    pub address: PciAddress,
+    /// 类别码，如 0x030200 表示 3D 控制器
+    pub class: u32,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub subsystem_vendor_id: u16,
+    pub subsystem_device_id: u16,
+    /// 没有 NUMA 或者内核报告 -1 时为 None
+    pub numa_node: Option<u32>,
+    /// 绑定的驱动，如 nvidia、amdgpu、vfio-pci
+    pub driver: Option<String>,
+    pub vendor_name: String,
+    pub device_name: String,
+}
+
+impl PciDevice {
+    /// 显示控制器（VGA、3D 控制器等）
+    pub fn is_display(&self) -> bool {
+        self.class >> 16 == 0x03
+    }
+
+    /// 处理加速器，昇腾 NPU 属于这一类
+    pub fn is_accelerator(&self) -> bool {
+        self.class >> 16 == 0x12
+    }
+
+    /// 网卡
+    pub fn is_network(&self) -> bool {
+        self.class >> 16 == 0x02
+    }
+
+    /// NVMe 控制器
+    pub fn is_nvme(&self) -> bool {
+        self.class >> 8 == 0x0108
+    }
+
+    /// 设备型号，pci.ids 中方括号里的部分是市场名称，优先使用
+    /// 如 "GA102 [GeForce RTX 3090]" 返回 "GeForce RTX 3090"；
+    /// 查不到名称时与 lspci 一致，如 "NVIDIA Corporation Device 2330"、"Device 10de:2330"
+    pub fn model(&self) -> String {
+        if self.device_name.is_empty() {
+            if self.vendor_name.is_empty() {
+                return format!("Device {:04x}:{:04x}", self.vendor_id, self.device_id);
+            }
+            return format!("{} Device {:04x}", self.vendor_name, self.device_id);
+        }
+        market_name(&self.device_name)
+    }
+}
+
+/// 从 pci.ids 查询设备型号，规则同 PciDevice::model，查不到时返回 None
+pub fn model_name(vendor_id: u16, device_id: u16) -> Option<String> {
+    PciIds::system().device(vendor_id, device_id).map(market_name)
+}
+
+fn market_name(device_name: &str) -> String {
+    match (device_name.find('['), device_name.rfind(']')) {
+        (Some(start), Some(end)) if start < end => device_name[start + 1..end].to_string(),
+        _ => device_name.to_string(),
+    }
+}
+
+/// pci.ids 中的厂商和设备名称
+#[derive(Debug, Default)]
+pub struct PciIds {
+    vendors: HashMap<u16, String>,
+    devices: HashMap<(u16, u16), String>,
+}
+
+impl PciIds {
+    /// 解析 pci.ids 格式的文本，只读取厂商和设备两级，忽略子系统和后面的类别表
+    pub fn parse(data: &str) -> Self {
+        let mut ids = PciIds::default();
+        let mut vendor: Option<u16> = None;
+
+        for line in data.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            // 类别表在文件末尾
+            if line.starts_with("C ") {
+                break;
+            }
+
+            if let Some(device_line) = line.strip_prefix('\t') {
+                // 两个制表符开头的是子系统
+                if device_line.starts_with('\t') {
+                    continue;
+                }
+                if let (Some(vendor), Some((id, name))) = (vendor, split_id(device_line)) {
+                    ids.devices.insert((vendor, id), name);
+                }
+            } else {
+                vendor = split_id(line).map(|(id, name)| {
+                    ids.vendors.insert(id, name);
+                    id
+                });
+            }
+        }
+
+        ids
+    }
+
+    /// 读取系统的 pci.ids，找不到时使用内置的精简数据，只加载一次
+    pub fn system() -> &'static PciIds {
+        static IDS: OnceLock<PciIds> = OnceLock::new();
+        IDS.get_or_init(|| PciIds::load(&PCI_IDS_PATHS))
+    }
+
+    /// 按顺序读取第一个存在的 pci.ids，都不存在时使用内置数据
+    fn load(paths: &[&str]) -> Self {
+        for path in paths {
+            if let Ok(data) = std::fs::read_to_string(path) {
+                return PciIds::parse(&data);
+            }
+        }
+        PciIds::parse(EMBEDDED_PCI_IDS)
+    }
+
+    pub fn vendor(&self, vendor_id: u16) -> Option<&str> {
+        self.vendors.get(&vendor_id).map(|name| name.as_str())
+    }
+
+    pub fn device(&self, vendor_id: u16, device_id: u16) -> Option<&str> {
+        self.devices.get(&(vendor_id, device_id)).map(|name| name.as_str())
+    }
+}
+
+fn split_id(line: &str) -> Option<(u16, String)> {
+    let (id, name) = line.split_once(char::is_whitespace)?;
+    let id = u16::from_str_radix(id, 16).ok()?;
+    Some((id, name.trim().to_string()))
+}
+
+/// 枚举本机的 PCI 设备
+#[cfg(not(target_os = "windows"))]
+pub fn scan() -> Vec<PciDevice> {
+    scan_root("/sys/bus/pci/devices", PciIds::system())
+}
+
+/// Windows 下没有 sysfs，从 wmic 的 PNPDeviceID 中解析显卡的厂商和设备 id，
+/// 如 PCI\VEN_10DE&DEV_2204&SUBSYS_147D10DE&REV_A1\4&1E6C0E7A&0&0008
+#[cfg(target_os = "windows")]
+pub fn scan() -> Vec<PciDevice> {
+    let output = match std::process::Command::new("cmd")
+        .args(&["/C", "wmic path win32_videocontroller get PNPDeviceID"])
+        .output() {
+            Ok(output) => output,
+            Err(_) => {
+                info!("wmic path win32_videocontroller get PNPDeviceID 执行失败");
+                return vec![];
+            }
+        };
+
+    let ids = PciIds::system();
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let field = |key: &str| {
+                let start = line.find(key)? + key.len();
+                let value: String = line[start..].chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+                u32::from_str_radix(&value, 16).ok()
+            };
+            let vendor_id = field("VEN_")? as u16;
+            let device_id = field("DEV_")? as u16;
+            let subsys = field("SUBSYS_").unwrap_or(0);
+            Some(PciDevice {
+                address: PciAddress::default(),
+                class: 0x030000,
+                vendor_id,
+                device_id,
+                subsystem_vendor_id: (subsys & 0xffff) as u16,
+                subsystem_device_id: (subsys >> 16) as u16,
+                numa_node: None,
+                driver: None,
+                vendor_name: ids.vendor(vendor_id).unwrap_or("").to_string(),
+                device_name: ids.device(vendor_id, device_id).unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 枚举指定目录下的 PCI 设备，测试时可以指向伪造的目录树
+pub fn scan_root<P: AsRef<Path>>(root: P, ids: &PciIds) -> Vec<PciDevice> {
+    let mut entries: Vec<_> = match std::fs::read_dir(root) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        Err(err) => {
+            info!("读取 PCI 设备失败: {}", err);
+            return vec![];
+        }
+    };
+    entries.sort();
+
+    entries
+        .iter()
+        .filter_map(|path| {
+            let address = PciAddress::parse(&path.file_name()?.to_string_lossy())?;
+            let vendor_id = sysfs::read_hex(path.join("vendor"))? as u16;
+            let device_id = sysfs::read_hex(path.join("device"))? as u16;
+
+            Some(PciDevice {
+                address,
+                class: sysfs::read_hex(path.join("class")).unwrap_or(0),
+                vendor_id,
+                device_id,
+                subsystem_vendor_id: sysfs::read_hex(path.join("subsystem_vendor")).unwrap_or(0) as u16,
+                subsystem_device_id: sysfs::read_hex(path.join("subsystem_device")).unwrap_or(0) as u16,
+                numa_node: sysfs::read_string(path.join("numa_node")).and_then(|node| node.parse::<u32>().ok()),
+                driver: sysfs::link_name(path.join("driver")),
+                vendor_name: ids.vendor(vendor_id).unwrap_or("").to_string(),
+                device_name: ids.device(vendor_id, device_id).unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_real_world_addresses() {
+        // (输入, sysfs 写法, nvidia-smi 写法, lspci 写法)
+        let cases = [
+            ("3b:00.0", "0000:3b:00.0", "00000000:3B:00.0", "3b:00.0"),
+            ("0000:a1:00.1", "0000:a1:00.1", "00000000:A1:00.1", "a1:00.1"),
+            ("00000000:3B:00.0", "0000:3b:00.0", "00000000:3B:00.0", "3b:00.0"),
+            // VMD 等设备的 domain 超过 4 位
+            ("10000:01:00.0", "10000:01:00.0", "00010000:01:00.0", "10000:01:00.0"),
+            // 旧版本上报的 gpu_bus_id 没有 function
+            ("00000000:65:00", "0000:65:00.0", "00000000:65:00.0", "65:00.0"),
+        ];
+
+        for (input, sysfs, nvidia_smi, lspci) in cases {
+            let address = PciAddress::parse(input).unwrap_or_else(|| panic!("{} 解析失败", input));
+            assert_eq!(address.to_string(), sysfs, "{}", input);
+            assert_eq!(address.nvidia_smi(), nvidia_smi, "{}", input);
+            assert_eq!(address.lspci(), lspci, "{}", input);
+            assert_eq!(PciAddress::parse(&address.to_string()), Some(address));
+        }
+
+        assert_eq!(PciAddress::parse("3b:00.0"), PciAddress::parse("00000000:3B:00.0"));
+        assert_eq!(PciAddress::parse("10000:01:00.0").unwrap().domain, 0x10000);
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        for input in ["", "N/A", "3b", "0000:3b:20.0", "0000:3b:00.8", "0000:3bb:00.0", "000000000:3b:00.0", "zz:00.0"] {
+            assert_eq!(PciAddress::parse(input), None, "{}", input);
+        }
+    }
+
+    // pci.ids 片段，包含注释、子系统行和末尾的类别表
+    const PCI_IDS: &str = "\
+# List of PCI ID's
+#
+#\tvendor  vendor_name
+#\t\tdevice  device_name
+#\t\t\tsubvendor subdevice  subsystem_name
+
+10de  NVIDIA Corporation
+\t20b2  GA100 [A100 SXM4 80GB]
+\t\t10de 1463  A100-SXM-80GB
+\t\t10de 147f  A100-SXM-80GB
+# 厂商之间的注释
+\t2330  GH100 [H100 SXM5 80GB]
+15b3  Mellanox Technologies
+\t101d  MT2892 Family [ConnectX-6 Dx]
+C 03  Display controller
+\t00  VGA compatible controller
+";
+
+    #[test]
+    fn parses_pci_ids() {
+        let ids = PciIds::parse(PCI_IDS);
+        assert_eq!(ids.vendor(0x10de), Some("NVIDIA Corporation"));
+        assert_eq!(ids.device(0x10de, 0x20b2), Some("GA100 [A100 SXM4 80GB]"));
+        // 子系统行不会被当成设备
+        assert_eq!(ids.device(0x10de, 0x1463), None);
+        assert_eq!(ids.device(0x10de, 0x2330), Some("GH100 [H100 SXM5 80GB]"));
+        assert_eq!(ids.device(0x15b3, 0x101d), Some("MT2892 Family [ConnectX-6 Dx]"));
+        // 类别表不会被当成厂商
+        assert_eq!(ids.vendors.len(), 2);
+        assert_eq!(ids.devices.len(), 3);
+    }
+
+    #[test]
+    fn missing_pci_ids_uses_embedded_data() {
+        let ids = PciIds::load(&["/nonexistent/pci.ids"]);
+        assert_eq!(ids.vendor(0x19e5), Some("Huawei Technologies Co., Ltd."));
+        assert_eq!(ids.device(0x10de, 0x2330), Some("GH100 [H100 SXM5 80GB]"));
+    }
+
+    #[test]
+    fn models_and_classes() {
+        let mut device = PciDevice {
+            class: 0x030200,
+            vendor_id: 0x10de,
+            device_id: 0x2330,
+            vendor_name: "NVIDIA Corporation".to_string(),
+            device_name: "GH100 [H100 SXM5 80GB]".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(device.model(), "H100 SXM5 80GB");
+        assert!(device.is_display());
+        assert!(!device.is_accelerator());
+
+        device.device_name = "".to_string();
+        assert_eq!(device.model(), "NVIDIA Corporation Device 2330");
+        device.vendor_name = "".to_string();
+        assert_eq!(device.model(), "Device 10de:2330");
+
+        let npu = PciDevice { class: 0x120000, vendor_id: 0x19e5, device_id: 0xd802, ..Default::default() };
+        assert!(npu.is_accelerator());
+        assert!(!npu.is_display());
+        let nvme = PciDevice { class: 0x010802, ..Default::default() };
+        assert!(nvme.is_nvme());
+    }
+
+    fn write(path: &Path, data: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn scans_fake_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("devices");
+        let drivers = dir.path().join("drivers");
+        std::fs::create_dir_all(drivers.join("nvidia")).unwrap();
+
+        let gpu = root.join("0000:3b:00.0");
+        write(&gpu.join("vendor"), "0x10de\n");
+        write(&gpu.join("device"), "0x20b2\n");
+        write(&gpu.join("class"), "0x030200\n");
+        write(&gpu.join("subsystem_vendor"), "0x10de\n");
+        write(&gpu.join("subsystem_device"), "0x1463\n");
+        write(&gpu.join("numa_node"), "1\n");
+        std::os::unix::fs::symlink(drivers.join("nvidia"), gpu.join("driver")).unwrap();
+
+        // 单 NUMA 的机器上内核报告 -1，没有绑定驱动
+        let nic = root.join("0000:17:00.0");
+        write(&nic.join("vendor"), "0x15b3\n");
+        write(&nic.join("device"), "0x101d\n");
+        write(&nic.join("class"), "0x020000\n");
+        write(&nic.join("numa_node"), "-1\n");
+
+        // pci.ids 里没有的设备
+        let unknown = root.join("0000:5e:00.0");
+        write(&unknown.join("vendor"), "0x1d17\n");
+        write(&unknown.join("device"), "0x3001\n");
+        write(&unknown.join("class"), "0x030000\n");
+
+        // 不是 PCI 地址的目录、缺少 vendor 的设备都跳过
+        write(&root.join("not-a-device/vendor"), "0x10de\n");
+        write(&root.join("0000:00:00.0/class"), "0x060000\n");
+
+        let devices = scan_root(&root, &PciIds::parse(PCI_IDS));
+        let addresses: Vec<String> = devices.iter().map(|device| device.address.to_string()).collect();
+        assert_eq!(addresses, vec!["0000:17:00.0", "0000:3b:00.0", "0000:5e:00.0"]);
+
+        let nic = &devices[0];
+        assert_eq!(nic.numa_node, None);
+        assert_eq!(nic.driver, None);
+        assert!(nic.is_network());
+        assert_eq!(nic.model(), "ConnectX-6 Dx");
+
+        let gpu = &devices[1];
+        assert_eq!((gpu.vendor_id, gpu.device_id), (0x10de, 0x20b2));
+        assert_eq!((gpu.subsystem_vendor_id, gpu.subsystem_device_id), (0x10de, 0x1463));
+        assert_eq!(gpu.class, 0x030200);
+        assert_eq!(gpu.numa_node, Some(1));
+        assert_eq!(gpu.driver.as_deref(), Some("nvidia"));
+        assert_eq!(gpu.vendor_name, "NVIDIA Corporation");
+        assert_eq!(gpu.model(), "A100 SXM4 80GB");
+
+        let unknown = &devices[2];
+        assert_eq!(unknown.vendor_name, "");
+        assert_eq!(unknown.model(), "Device 1d17:3001");
+
+        // 没有 pci.ids 时名称为空，型号退回到十六进制 id
+        let devices = scan_root(&root, &PciIds::default());
+        assert_eq!(devices[1].model(), "Device 10de:20b2");
+    }
+
+    #[test]
+    fn serializes_as_sysfs_string() {
+        let address = PciAddress::parse("00000000:3B:00.0").unwrap();
+        assert_eq!(serde_json::to_string(&address).unwrap(), "\"0000:3b:00.0\"");
+        assert_eq!(serde_json::from_str::<PciAddress>("\"3b:00.0\"").unwrap(), address);
+    }
+}
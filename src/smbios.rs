@@ -0,0 +1,436 @@
+//! 直接解析 /sys/firmware/dmi/tables/DMI 中的 SMBIOS 结构，获取内存条信息，不依赖 dmidecode
+
+use std::error::Error;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+/// 一个内存插槽，对应 SMBIOS type 17，没有插内存条时 installed 为 false
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MemoryModule {
+    /// 插槽名，如 "DIMM_A1"、"P0 CHANNEL A"
+    pub locator: String,
+    pub bank_locator: String,
+    pub installed: bool,
+    /// 单位字节，空插槽或未知时为 None
+    pub size_bytes: Option<u64>,
+    /// DDR4、DDR5、LPDDR5 等
+    pub memory_type: String,
+    /// DIMM、SODIMM 等
+    pub form_factor: String,
+    /// 标称速率，单位 MT/s
+    pub speed_mts: Option<u32>,
+    /// 实际运行速率，单位 MT/s
+    pub configured_speed_mts: Option<u32>,
+    pub manufacturer: String,
+    pub serial_number: String,
+    pub part_number: String,
+    pub rank: Option<u8>,
+    /// 带缓冲的 RDIMM / LRDIMM
+    pub registered: bool,
+    /// 所在内存阵列（type 16）的纠错方式，如 "Multi-bit ECC"、"None"
+    pub error_correction: String,
+    /// 总位宽大于数据位宽，或者内存阵列声明了 ECC
+    pub ecc: Option<bool>,
+}
+
+/// 一个 SMBIOS 结构：格式化区域和字符串表
+struct Structure<'a> {
+    kind: u8,
+    handle: u16,
+    data: &'a [u8],
+    strings: Vec<String>,
+}
+
+impl Structure<'_> {
+    fn byte(&self, offset: usize) -> Option<u8> {
+        self.data.get(offset).copied()
+    }
+
+    fn word(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn dword(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// 字符串字段保存的是从 1 开始的序号，0 表示没有
+    fn string(&self, offset: usize) -> String {
+        match self.byte(offset) {
+            Some(index) if index > 0 => self.strings.get(index as usize - 1).cloned().unwrap_or_default(),
+            _ => "".to_string(),
+        }
+    }
+}
+
+/// 读取并解析 SMBIOS 表，需要 root 权限
+pub fn read_memory_modules<P: AsRef<Path>>(path: P) -> Result<Vec<MemoryModule>, Box<dyn Error>> {
+    let table = std::fs::read(path)?;
+    Ok(parse_memory_modules(&table))
+}
+
+/// 解析 SMBIOS 结构表中的 type 16（内存阵列）和 type 17（内存设备）
+pub fn parse_memory_modules(table: &[u8]) -> Vec<MemoryModule> {
+    let structures = structures(table);
+
+    structures
+        .iter()
+        .filter(|structure| structure.kind == 17)
+        .filter_map(|device| {
+            let array = device
+                .word(0x04)
+                .and_then(|handle| structures.iter().find(|s| s.kind == 16 && s.handle == handle));
+            // 只保留系统内存，跳过显存、闪存等阵列
+            if array.and_then(|array| array.byte(0x05)).is_some_and(|usage| usage != 0x03) {
+                return None;
+            }
+            Some(memory_module(device, array))
+        })
+        .collect()
+}
+
+fn memory_module(device: &Structure, array: Option<&Structure>) -> MemoryModule {
+    let size_bytes = module_size(device);
+    let error_correction = array.and_then(|array| array.byte(0x06)).map(error_correction).unwrap_or_default();
+
+    // 位宽 0xFFFF 表示未知
+    let total_width = device.word(0x08).filter(|width| *width != 0xffff);
+    let data_width = device.word(0x0a).filter(|width| *width != 0xffff);
+    let ecc = match (total_width, data_width) {
+        (Some(total), Some(data)) if data > 0 => Some(total > data),
+        _ => match error_correction.as_str() {
+            "" | "Unknown" | "Other" => None,
+            "None" => Some(false),
+            _ => Some(true),
+        },
+    };
+
+    MemoryModule {
+        locator: device.string(0x10),
+        bank_locator: device.string(0x11),
+        installed: size_bytes.is_some_and(|size| size > 0),
+        size_bytes: size_bytes.filter(|size| *size > 0),
+        memory_type: device.byte(0x12).map(memory_type).unwrap_or_default().to_string(),
+        form_factor: device.byte(0x0e).map(form_factor).unwrap_or_default().to_string(),
+        speed_mts: speed(device, 0x15, 0x54),
+        configured_speed_mts: speed(device, 0x20, 0x58),
+        manufacturer: device.string(0x17),
+        serial_number: device.string(0x18),
+        part_number: device.string(0x1a),
+        rank: device.byte(0x1b).map(|attributes| attributes & 0x0f).filter(|rank| *rank > 0),
+        registered: device.word(0x13).is_some_and(|detail| detail & (1 << 13) != 0),
+        error_correction,
+        ecc,
+    }
+}
+
+/// Size 为 0 表示空插槽，0xFFFF 表示未知，0x7FFF 表示实际大小在 Extended Size 中（单位 MB），
+/// 最高位为 1 时单位是 KB，否则是 MB
+fn module_size(device: &Structure) -> Option<u64> {
+    let size = device.word(0x0c)?;
+    match size {
+        0xffff => None,
+        0x7fff => device.dword(0x1c).map(|mb| (mb as u64 & 0x7fff_ffff) << 20),
+        size if size & 0x8000 != 0 => Some(((size & 0x7fff) as u64) << 10),
+        size => Some((size as u64) << 20),
+    }
+}
+
+/// 速率 0 表示未知，0xFFFF 表示实际值在扩展字段中
+fn speed(device: &Structure, offset: usize, extended: usize) -> Option<u32> {
+    match device.word(offset)? {
+        0 => None,
+        0xffff => device.dword(extended).map(|speed| speed & 0x7fff_ffff).filter(|speed| *speed > 0),
+        speed => Some(speed as u32),
+    }
+}
+
+/// 按 SMBIOS 规范切分结构表，遇到 type 127（表结束）或数据不完整时停止
+fn structures(table: &[u8]) -> Vec<Structure<'_>> {
+    let mut structures = vec![];
+    let mut offset = 0;
+
+    while offset + 4 <= table.len() {
+        let kind = table[offset];
+        let length = table[offset + 1] as usize;
+        if length < 4 || offset + length > table.len() {
+            break;
+        }
+        let handle = u16::from_le_bytes([table[offset + 2], table[offset + 3]]);
+        let data = &table[offset..offset + length];
+
+        // 字符串表由以 0 结尾的字符串组成，整个表以两个 0 结尾
+        let mut end = offset + length;
+        while end + 1 < table.len() && !(table[end] == 0 && table[end + 1] == 0) {
+            end += 1;
+        }
+        let strings = table[offset + length..end.min(table.len())]
+            .split(|byte| *byte == 0)
+            .filter(|string| !string.is_empty())
+            .map(|string| String::from_utf8_lossy(string).trim().to_string())
+            .collect();
+
+        structures.push(Structure { kind, handle, data, strings });
+        if kind == 127 {
+            break;
+        }
+        offset = end + 2;
+    }
+
+    structures
+}
+
+fn memory_type(value: u8) -> &'static str {
+    match value {
+        0x01 => "Other",
+        0x02 => "Unknown",
+        0x03 => "DRAM",
+        0x04 => "EDRAM",
+        0x05 => "VRAM",
+        0x06 => "SRAM",
+        0x07 => "RAM",
+        0x08 => "ROM",
+        0x09 => "Flash",
+        0x0a => "EEPROM",
+        0x0b => "FEPROM",
+        0x0c => "EPROM",
+        0x0d => "CDRAM",
+        0x0e => "3DRAM",
+        0x0f => "SDRAM",
+        0x10 => "SGRAM",
+        0x11 => "RDRAM",
+        0x12 => "DDR",
+        0x13 => "DDR2",
+        0x14 => "DDR2 FB-DIMM",
+        0x18 => "DDR3",
+        0x19 => "FBD2",
+        0x1a => "DDR4",
+        0x1b => "LPDDR",
+        0x1c => "LPDDR2",
+        0x1d => "LPDDR3",
+        0x1e => "LPDDR4",
+        0x1f => "Logical non-volatile device",
+        0x20 => "HBM",
+        0x21 => "HBM2",
+        0x22 => "DDR5",
+        0x23 => "LPDDR5",
+        0x24 => "HBM3",
+        _ => "",
+    }
+}
+
+fn form_factor(value: u8) -> &'static str {
+    match value {
+        0x01 => "Other",
+        0x02 => "Unknown",
+        0x03 => "SIMM",
+        0x04 => "SIP",
+        0x05 => "Chip",
+        0x06 => "DIP",
+        0x07 => "ZIP",
+        0x08 => "Proprietary Card",
+        0x09 => "DIMM",
+        0x0a => "TSOP",
+        0x0b => "Row of chips",
+        0x0c => "RIMM",
+        0x0d => "SODIMM",
+        0x0e => "SRIMM",
+        0x0f => "FB-DIMM",
+        0x10 => "Die",
+        _ => "",
+    }
+}
+
+fn error_correction(value: u8) -> String {
+    match value {
+        0x01 => "Other",
+        0x02 => "Unknown",
+        0x03 => "None",
+        0x04 => "Parity",
+        0x05 => "Single-bit ECC",
+        0x06 => "Multi-bit ECC",
+        0x07 => "CRC",
+        _ => "",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 一个 SMBIOS 结构：格式化区域后面跟字符串表，没有字符串时以两个 0 结尾
+    fn structure(kind: u8, handle: u16, body: &[u8], strings: &[&str]) -> Vec<u8> {
+        let mut bytes = vec![kind, (body.len() + 4) as u8];
+        bytes.extend_from_slice(&handle.to_le_bytes());
+        bytes.extend_from_slice(body);
+        if strings.is_empty() {
+            bytes.push(0);
+        }
+        for string in strings {
+            bytes.extend_from_slice(string.as_bytes());
+            bytes.push(0);
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    /// type 16，use 为 0x03 系统内存、0x04 显存
+    fn memory_array(handle: u16, usage: u8, error_correction: u8) -> Vec<u8> {
+        // 格式化区域从 0x04 开始：位置（主板）、用途、纠错方式
+        let mut body = vec![0u8; 0x17 - 4];
+        body[..3].copy_from_slice(&[0x03, usage, error_correction]);
+        structure(16, handle, &body, &[])
+    }
+
+    /// SMBIOS 3.3 的 type 17，长度 0x5C
+    #[derive(Default)]
+    struct Device {
+        array: u16,
+        size: u16,
+        extended_size: u32,
+        speed: u16,
+        configured_speed: u16,
+        extended_speed: u32,
+        extended_configured_speed: u32,
+        memory_type: u8,
+        type_detail: u16,
+        attributes: u8,
+        total_width: u16,
+        data_width: u16,
+    }
+
+    fn memory_device(handle: u16, device: Device, strings: &[&str]) -> Vec<u8> {
+        let mut body = vec![0u8; 0x5c - 4];
+        let mut put = |offset: usize, bytes: &[u8]| body[offset - 4..offset - 4 + bytes.len()].copy_from_slice(bytes);
+        put(0x04, &device.array.to_le_bytes());
+        put(0x08, &device.total_width.to_le_bytes());
+        put(0x0a, &device.data_width.to_le_bytes());
+        put(0x0c, &device.size.to_le_bytes());
+        put(0x0e, &[0x09]);
+        // 字符串序号：插槽、Bank、厂商、序列号、资产标签、料号
+        put(0x10, &[1, 2]);
+        put(0x12, &[device.memory_type]);
+        put(0x13, &device.type_detail.to_le_bytes());
+        put(0x15, &device.speed.to_le_bytes());
+        put(0x17, &[3, 4, 5, 6]);
+        put(0x1b, &[device.attributes]);
+        put(0x1c, &device.extended_size.to_le_bytes());
+        put(0x20, &device.configured_speed.to_le_bytes());
+        put(0x54, &device.extended_speed.to_le_bytes());
+        put(0x58, &device.extended_configured_speed.to_le_bytes());
+        structure(17, handle, &body, strings)
+    }
+
+    fn table() -> Vec<u8> {
+        let mut table = vec![];
+        table.extend(memory_array(0x1000, 0x03, 0x06));
+        // 32 GB 的 RDIMM，Size 放不下，写在 Extended Size 中
+        table.extend(memory_device(0x1100, Device {
+            array: 0x1000,
+            size: 0x7fff,
+            extended_size: 32768,
+            speed: 3200,
+            configured_speed: 2933,
+            memory_type: 0x1a,
+            type_detail: 1 << 13,
+            attributes: 2,
+            total_width: 72,
+            data_width: 64,
+            ..Default::default()
+        }, &["CPU0_DIMM_A1", "P0_Node0_Channel0_Dimm0", "Samsung", "03A1B2C3", "Not Specified", "M393A4K40DB3-CWE"]));
+        // 空插槽
+        table.extend(memory_device(0x1101, Device {
+            array: 0x1000,
+            memory_type: 0x02,
+            total_width: 0xffff,
+            data_width: 0xffff,
+            ..Default::default()
+        }, &["CPU0_DIMM_A2", "P0_Node0_Channel0_Dimm1", "NO DIMM", "NO DIMM", "NO DIMM", "NO DIMM"]));
+        // 以 KB 为单位的小容量模块，速率写在扩展字段中
+        table.extend(memory_device(0x1102, Device {
+            array: 0x1000,
+            size: 0x8000 | 512,
+            speed: 0xffff,
+            configured_speed: 0xffff,
+            extended_speed: 70000,
+            extended_configured_speed: 68000,
+            memory_type: 0x22,
+            total_width: 64,
+            data_width: 64,
+            ..Default::default()
+        }, &["DIMM_B1", "BANK 1", "SK Hynix", "12345678", "", "HMCG88AEBRA"]));
+        // 显存阵列上的设备不算内存条
+        table.extend(memory_array(0x2000, 0x04, 0x03));
+        table.extend(memory_device(0x2100, Device {
+            array: 0x2000,
+            size: 8192,
+            ..Default::default()
+        }, &["VRAM", "", "", "", "", ""]));
+        table.extend(structure(127, 0xfeff, &[], &[]));
+        table
+    }
+
+    #[test]
+    fn parses_extended_size() {
+        let modules = parse_memory_modules(&table());
+        assert_eq!(modules.len(), 3);
+
+        let module = &modules[0];
+        assert_eq!(module.locator, "CPU0_DIMM_A1");
+        assert_eq!(module.bank_locator, "P0_Node0_Channel0_Dimm0");
+        assert!(module.installed);
+        assert_eq!(module.size_bytes, Some(32 << 30));
+        assert_eq!(module.memory_type, "DDR4");
+        assert_eq!(module.form_factor, "DIMM");
+        assert_eq!(module.speed_mts, Some(3200));
+        assert_eq!(module.configured_speed_mts, Some(2933));
+        assert_eq!(module.manufacturer, "Samsung");
+        assert_eq!(module.serial_number, "03A1B2C3");
+        assert_eq!(module.part_number, "M393A4K40DB3-CWE");
+        assert_eq!(module.rank, Some(2));
+        assert!(module.registered);
+        assert_eq!(module.error_correction, "Multi-bit ECC");
+        assert_eq!(module.ecc, Some(true));
+    }
+
+    #[test]
+    fn parses_empty_slot() {
+        let module = &parse_memory_modules(&table())[1];
+        assert_eq!(module.locator, "CPU0_DIMM_A2");
+        assert!(!module.installed);
+        assert_eq!(module.size_bytes, None);
+        assert_eq!(module.speed_mts, None);
+        assert_eq!(module.rank, None);
+        assert!(!module.registered);
+        // 位宽未知时按内存阵列的纠错方式判断
+        assert_eq!(module.ecc, Some(true));
+    }
+
+    #[test]
+    fn parses_kb_size_and_extended_speed() {
+        let module = &parse_memory_modules(&table())[2];
+        assert!(module.installed);
+        assert_eq!(module.size_bytes, Some(512 << 10));
+        assert_eq!(module.memory_type, "DDR5");
+        assert_eq!(module.speed_mts, Some(70000));
+        assert_eq!(module.configured_speed_mts, Some(68000));
+        assert_eq!(module.ecc, Some(false));
+    }
+
+    #[test]
+    fn unknown_size_and_truncated_tables() {
+        let mut table = memory_device(0x1100, Device { size: 0xffff, ..Default::default() }, &["DIMM_A1"]);
+        assert_eq!(parse_memory_modules(&table)[0].size_bytes, None);
+        assert!(!parse_memory_modules(&table)[0].installed);
+
+        // 截断的结构直接忽略
+        table.truncate(20);
+        assert!(parse_memory_modules(&table).is_empty());
+        assert!(parse_memory_modules(&[]).is_empty());
+    }
+}
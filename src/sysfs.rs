@@ -0,0 +1,69 @@
+//! 读取 sysfs / procfs 文件的小工具，读不到时返回 None 而不是报错
+
+use std::path::{Path, PathBuf};
+
+/// 读取文件内容并去掉首尾空白，文件不存在、无权限或内容为空时返回 None
+pub(crate) fn read_string<P: AsRef<Path>>(path: P) -> Option<String> {
+    let data = std::fs::read_to_string(path).ok()?;
+    let data = data.trim();
+    if data.is_empty() {
+        None
+    } else {
+        Some(data.to_string())
+    }
+}
+
+/// 读取十进制整数
+pub(crate) fn read_u64<P: AsRef<Path>>(path: P) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+/// 读取 0x 开头的十六进制整数，如 PCI 的 vendor、device
+pub(crate) fn read_hex<P: AsRef<Path>>(path: P) -> Option<u32> {
+    let data = read_string(path)?;
+    u32::from_str_radix(data.trim_start_matches("0x"), 16).ok()
+}
+
+/// 符号链接指向的文件名，如 device/driver -> ../../bus/pci/drivers/amdgpu 返回 amdgpu
+pub(crate) fn link_name<P: AsRef<Path>>(path: P) -> Option<String> {
+    let target = std::fs::read_link(path).ok()?;
+    Some(target.file_name()?.to_string_lossy().into_owned())
+}
+
+/// 列出目录下名称以 prefix 开头、后面紧跟数字的条目，按数字排序
+/// 如 numbered_entries("/sys/class/drm", "card") 返回 card0、card1，跳过 card0-DP-1
+pub(crate) fn numbered_entries<P: AsRef<Path>>(dir: P, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let mut entries: Vec<(u32, PathBuf)> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let number = name.strip_prefix(prefix)?.parse::<u32>().ok()?;
+                Some((number, entry.path()))
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    entries.sort_by_key(|(number, _)| *number);
+    entries
+}
+
+/// 解析 CPU 列表，如 "0-31,64-95" 或 sysfs 的 cpulist 文件内容
+pub(crate) fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut cpus = vec![];
+    for range in list.trim().split(',').map(|range| range.trim()).filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(cpu) = range.parse::<u32>() {
+                    cpus.push(cpu);
+                }
+            }
+        }
+    }
+    cpus
+}